@@ -0,0 +1,71 @@
+// Copyright (c) 2025 Eclipse Foundation and others.
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use carmate_service::health::{self as service, Connection, HealthMessage};
+use carmate_service::http::Response;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use up_rust::StaticUriProvider;
+
+const INTERVAL: Duration = Duration::from_secs(5);
+// Port of the HTTP /healthz and /readyz endpoints
+pub(crate) const HTTP_PORT: u16 = 9103;
+// uProtocol identity of the provider
+const AUTHORITY: &str = "carlaprovider";
+const UE_ID: u32 = 0;
+const UE_VERSION: u8 = 1;
+pub(crate) const SERVICE: &str = "carla_provider";
+
+// Connections of the provider
+pub(crate) struct Health {
+    started: Instant,
+    pub kuksa: Connection,
+    pub carla: Connection,
+}
+
+impl Health {
+    pub(crate) fn new() -> Self {
+        Health {
+            started: Instant::now(),
+            kuksa: Connection::new("Kuksa"),
+            carla: Connection::new("CARLA"),
+        }
+    }
+
+    pub(crate) fn connections(&self) -> [&Connection; 2] {
+        [&self.kuksa, &self.carla]
+    }
+
+    fn message(&self) -> HealthMessage {
+        HealthMessage::new(SERVICE, &self.connections(), self.started)
+    }
+}
+
+// Container probes: /healthz while the process serves requests, /readyz while all connections are up
+pub(crate) fn probe(path: &str, health: &Health) -> Response {
+    service::probe(path, &health.message())
+}
+
+// Publish the health message over uProtocol every interval. The Zenoh session is
+// created here and retried, so a missing Zenoh router never blocks the simulation.
+pub(crate) async fn publish_periodically(health: Arc<Health>, zenoh_config: zenoh::Config) {
+    let transport = service::zenoh_transport(AUTHORITY, zenoh_config, INTERVAL).await;
+    let uri_provider = StaticUriProvider::new(AUTHORITY, UE_ID, UE_VERSION);
+    service::publish_periodically(&transport, &uri_provider, INTERVAL, || health.message()).await;
+}
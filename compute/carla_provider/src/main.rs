@@ -17,6 +17,8 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
+mod health;
+
 use std::sync::{
     Arc,
     atomic::{AtomicBool, Ordering},
@@ -24,13 +26,20 @@ use std::sync::{
 use std::sync::Mutex as StdMutex;
 use std::time::Duration;
 
+use crate::health::Health;
+use carmate_service::telemetry::{self, LogFormat};
+use carmate_service::zenoh_options::{ZenohArgs, ZenohOptions};
+use carmate_service::{http, shutdown};
 use carla::client::{ActorBase, Sensor, Client, Vehicle};
 use carla::rpc::AttachmentType;
+use clap::Parser;
 use kuksa_rust_sdk::kuksa::common::ClientTraitV2;
 use kuksa_rust_sdk::kuksa::val::v2::KuksaClientV2;
 use kuksa_rust_sdk::v2_proto;
 use nalgebra::{Vector3, Isometry3, Translation3, UnitQuaternion};
-use tokio::{sync::Mutex, time::sleep};
+use tokio::{sync::Mutex, task::JoinSet, time::sleep};
+use tracing::{Instrument, debug, error, info, info_span, warn};
+use up_rust::UUID;
 
 const CLIENT_TIME_MS: u64 = 5_000;
 const CARLA_HOST: &str = "192.168.43.249";
@@ -38,6 +47,21 @@ const CARLA_PORT: u16 = 2000;
 const TM_PORT: u16 = 8000;
 const KUKSA_HOST: &str = "http://192.168.43.241:55555";
 // const KUKSA_HOST: &str = "http://localhost:55555";
+// Log filter unless set with RUST_LOG
+const DEFAULT_LOG_LEVEL: &str = "info";
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Log output format
+    #[clap(long, value_enum, env = "LOG_FORMAT", default_value_t)]
+    log_format: LogFormat,
+    /// OpenTelemetry collector to export spans to over OTLP/gRPC, e.g. http://localhost:4317
+    #[clap(long, env = "OTEL_EXPORTER_OTLP_ENDPOINT")]
+    otlp_endpoint: Option<String>,
+    #[clap(flatten)]
+    zenoh: ZenohArgs,
+}
 
 // Shared GNSS state (updated by sensor listener, read by tick loop)
 #[derive(Default, Clone)]
@@ -54,9 +78,14 @@ fn velocity_to_speed_kmh(v: Vector3<f32>) -> f32 {
 }
 
 // KUKSA single-signal publisher as float (call this once per signal)
-async fn publish_float_signal(client: Arc<Mutex<KuksaClientV2>>, path: &str, value: f32) {
+async fn publish_float_signal(
+    client: Arc<Mutex<KuksaClientV2>>,
+    health: &Health,
+    path: &str,
+    value: f32,
+) {
     let mut c = client.lock().await;
-    if let Err(e) = c
+    match c
         .publish_value(
             path.to_owned(),
             v2_proto::Value {
@@ -65,14 +94,26 @@ async fn publish_float_signal(client: Arc<Mutex<KuksaClientV2>>, path: &str, val
         )
         .await
     {
-        log::warn!("Publish {path} failed: {e}");
+        Ok(_) => {
+            health.kuksa.succeeded();
+            debug!(path, value, "Published");
+        }
+        Err(e) => {
+            health.kuksa.failed();
+            warn!(path, error = %e, "Publish failed");
+        }
     }
 }
 
 // KUKSA single-signal publisher as double (call this once per signal)
-async fn publish_double_signal(client: Arc<Mutex<KuksaClientV2>>, path: &str, value: f64) {
+async fn publish_double_signal(
+    client: Arc<Mutex<KuksaClientV2>>,
+    health: &Health,
+    path: &str,
+    value: f64,
+) {
     let mut c = client.lock().await;
-    if let Err(e) = c
+    match c
         .publish_value(
             path.to_owned(),
             v2_proto::Value {
@@ -81,35 +122,69 @@ async fn publish_double_signal(client: Arc<Mutex<KuksaClientV2>>, path: &str, va
         )
         .await
     {
-        log::warn!("Publish {path} failed: {e}");
+        Ok(_) => {
+            health.kuksa.succeeded();
+            debug!(path, value, "Published");
+        }
+        Err(e) => {
+            health.kuksa.failed();
+            warn!(path, error = %e, "Publish failed");
+        }
     }
 }
 
 // Main
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Logging
-    pretty_env_logger::init();
+    let args = Args::parse();
+
+    // Structured logging, optionally exported to an OpenTelemetry collector
+    let telemetry = telemetry::init(
+        health::SERVICE,
+        DEFAULT_LOG_LEVEL,
+        args.log_format,
+        args.otlp_endpoint.as_deref(),
+    )?;
+    // Zenoh session of the health messages
+    let zenoh_config = ZenohOptions::from(args.zenoh).build()?;
 
     // KUKSA client wrapped so we can share it from spawned tasks without blocking the tick loop
     let v2_client = Arc::new(Mutex::new(KuksaClientV2::from_host(KUKSA_HOST)));
 
-    // Ctrl+C → graceful shutdown
+    // Health message over uProtocol and HTTP probes for the container runtime
+    let health = Arc::new(Health::new());
+    tokio::spawn(health::publish_periodically(Arc::clone(&health), zenoh_config));
+    {
+        let health = Arc::clone(&health);
+        tokio::spawn(async move {
+            if let Err(e) = http::serve(health::HTTP_PORT, move |path| {
+                health::probe(path, &health)
+            })
+            .await
+            {
+                error!(
+                    port = health::HTTP_PORT,
+                    error = %e,
+                    "Serving health probes failed"
+                );
+            }
+        });
+    }
+
+    // SIGTERM / SIGINT → graceful shutdown
     let running = Arc::new(AtomicBool::new(true));
     {
         let r = running.clone();
-        ctrlc::set_handler(move || {
-            log::warn!("Cancelled by user. Bye!");
+        tokio::spawn(async move {
+            if let Err(e) = shutdown::requested().await {
+                error!(error = %e, "Waiting for shutdown signals failed");
+            }
             r.store(false, Ordering::SeqCst);
-        })?;
+        });
     }
 
     // Connect to CARLA
-    log::info!(
-        "Connecting to the CARLA server at {}:{}…",
-        CARLA_HOST,
-        CARLA_PORT
-    );
+    info!(host = CARLA_HOST, port = CARLA_PORT, "Connecting to the CARLA server");
     let mut carla_client = Client::connect(CARLA_HOST, CARLA_PORT, None);
     carla_client.set_timeout(Duration::from_millis(CLIENT_TIME_MS));
 
@@ -125,10 +200,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     carla_settings.fixed_delta_seconds = Some(0.05);
     carla_world.apply_settings(&carla_settings, Duration::from_millis(CLIENT_TIME_MS));
 
-    log::info!(
-        "World settings: synchronous={}, fixed_delta_seconds={:?}",
-        carla_settings.synchronous_mode,
-        carla_settings.fixed_delta_seconds
+    info!(
+        synchronous = carla_settings.synchronous_mode,
+        fixed_delta_seconds = ?carla_settings.fixed_delta_seconds,
+        "World settings applied"
     );
 
     // Map & blueprints
@@ -187,18 +262,18 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     });
                 }
             } else {
-                log::warn!("GNSS blueprint not found");
+                warn!("GNSS blueprint not found");
             }
 
             vehicle.set_autopilot_opt(true, TM_PORT);
-            log::info!("Vehicle spawned and autopilot enabled on TM port {}", TM_PORT);
+            info!(port = TM_PORT, "Vehicle spawned and autopilot enabled");
 
             carla_vehicle = Some(vehicle);
         } else {
-            log::error!("Vehicle blueprint not found");
+            error!("Vehicle blueprint not found");
         }
     } else {
-        log::error!("No recommended spawn points available");
+        error!("No recommended spawn points available");
     }
 
     // Weather animation: wetness goes up/down between 0..20
@@ -206,6 +281,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut direction_up = true;
     let mut cnt: i32 = 0;
     let mut tick: u64 = 0;
+    // Publishes still running, all awaited on shutdown so no value is lost
+    let mut publishes = JoinSet::new();
 
     // Main loop
     while running.load(Ordering::SeqCst) {
@@ -245,68 +322,119 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 alt = s.alt as f32;
             }
 
-            log::info!(
-                "Speed: {:.1} km/h | lat: {:.6}, lon: {:.6}, alt: {:.1} m",
-                speed_kmh,
-                lat,
-                lon,
-                alt
-            );
-
             // Update & apply weather
             carla_weather.wetness = cnt as f32; // 0..20 (CARLA accepts 0..100)
-            log::info!("Wetness: {} %", carla_weather.wetness);
             carla_world.set_weather(&carla_weather);
 
+            // One span per sample, so its publishes can be followed into KUKSA
+            let span = info_span!(
+                "sample",
+                source = "carla",
+                correlation_id = %UUID::build().to_hyphenated_string(),
+            );
+            span.in_scope(|| {
+                info!(
+                    speed_kmh,
+                    lat,
+                    lon,
+                    alt,
+                    wetness = carla_weather.wetness,
+                    "Sample"
+                )
+            });
+
             // Capture scalars for async publish
             let wet = carla_weather.wetness;
             let client = Arc::clone(&v2_client);
+            let health = Arc::clone(&health);
 
             // Offload 5 publishes using the single helper (non-blocking)
-            tokio::spawn(async move {
-                publish_float_signal(Arc::clone(&client), "Vehicle.Exterior.Humidity", wet).await;
-                publish_float_signal(Arc::clone(&client), "Vehicle.Speed", speed_kmh).await;
+            let publish = async move {
+                publish_float_signal(
+                    Arc::clone(&client),
+                    &health,
+                    "Vehicle.Exterior.Humidity",
+                    wet,
+                )
+                .await;
+                publish_float_signal(Arc::clone(&client), &health, "Vehicle.Speed", speed_kmh)
+                    .await;
                 publish_double_signal(
                     Arc::clone(&client),
+                    &health,
                     "Vehicle.CurrentLocation.Latitude",
                     lat as f64,
                 )
                 .await;
                 publish_double_signal(
                     Arc::clone(&client),
+                    &health,
                     "Vehicle.CurrentLocation.Longitude",
                     lon as f64,
                 )
                 .await;
                 publish_double_signal(
                     Arc::clone(&client),
+                    &health,
                     "Vehicle.CurrentLocation.Altitude",
                     alt as f64,
                 )
                 .await;
-            });
+            };
+            publishes.spawn(publish.instrument(span));
+            // Forget the publishes that completed since the last sample
+            while publishes.try_join_next().is_some() {}
         }
 
         // CPU throttle (simulation time advances only via tick())
         sleep(Duration::from_millis(50)).await;
 
-        // Sync advance: world then Traffic Manager
-        carla_world.tick();
-        let _ = carla_tm.synchronous_tick();
+        // Sync advance: world then Traffic Manager, a tick that timed out counts as a failure
+        let world_ticked = carla_world
+            .tick_or_timeout(Duration::from_millis(CLIENT_TIME_MS))
+            .is_some();
+        let tm_ticked = carla_tm.synchronous_tick();
+        if world_ticked && tm_ticked {
+            health.carla.succeeded();
+        } else {
+            health.carla.failed();
+            warn!(tick, world_ticked, tm_ticked, "Advancing the simulation failed");
+        }
     }
 
-    // Cleanup sensor
-    if let Some(sensor) = gnss_keepalive.take() {
-        // Stop delivering callbacks
+    // Drain pending publishes
+    while let Some(result) = publishes.join_next().await {
+        if let Err(err) = result {
+            warn!(error = %err, "Publishing values failed");
+        }
+    }
+
+    // Stop delivering sensor callbacks
+    if let Some(sensor) = gnss_keepalive.as_ref() {
         sensor.stop();
     }
 
-    // Restore async world settings
+    // Restore async world settings and release the Traffic Manager
     let mut s = carla_world.settings();
     s.synchronous_mode = false;
     s.fixed_delta_seconds = None;
     carla_world.apply_settings(&s, Duration::from_millis(CLIENT_TIME_MS));
+    carla_tm.set_synchronous_mode(false);
+
+    // Destroy spawned actors, so no orphans are left in the simulation
+    if let Some(sensor) = gnss_keepalive.take() {
+        if !sensor.destroy() {
+            warn!("Destroying the GNSS sensor failed");
+        }
+    }
+    if let Some(vehicle) = carla_vehicle.take() {
+        vehicle.set_autopilot_opt(false, TM_PORT);
+        if !vehicle.destroy() {
+            warn!("Destroying the vehicle failed");
+        }
+    }
 
-    log::info!("Shutdown complete.");
+    info!("Shutdown complete.");
+    telemetry.shutdown();
     Ok(())
 }
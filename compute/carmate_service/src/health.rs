@@ -0,0 +1,252 @@
+// Copyright (c) 2025 Eclipse Foundation and others.
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::http::Response;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicI64, AtomicU8, AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+use up_rust::LocalUriProvider;
+use up_rust::UMessageBuilder;
+use up_rust::UPayloadFormat;
+use up_rust::UTransport;
+use up_transport_zenoh::UPTransportZenoh;
+
+// Topic of the health message, the same resource id is used by every CarMate service
+pub const RESOURCE_ID: u16 = 0x8FFF;
+
+// Current time in milliseconds since the Unix epoch
+pub fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+#[repr(u8)]
+pub enum ConnectionState {
+    Connecting = 0,
+    Connected = 1,
+    Disconnected = 2,
+}
+
+impl ConnectionState {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => ConnectionState::Connected,
+            2 => ConnectionState::Disconnected,
+            _ => ConnectionState::Connecting,
+        }
+    }
+}
+
+// State of one supervised connection, readable from any task
+pub struct Connection {
+    name: &'static str,
+    state: AtomicU8,
+    reconnects: AtomicU64,
+    // Last successful read or send in milliseconds since the Unix epoch, 0 if none yet
+    last_success: AtomicI64,
+    errors: AtomicU64,
+}
+
+impl Connection {
+    pub const fn new(name: &'static str) -> Self {
+        Connection {
+            name,
+            state: AtomicU8::new(ConnectionState::Connecting as u8),
+            reconnects: AtomicU64::new(0),
+            last_success: AtomicI64::new(0),
+            errors: AtomicU64::new(0),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn state(&self) -> ConnectionState {
+        ConnectionState::from_u8(self.state.load(Ordering::SeqCst))
+    }
+
+    pub fn reconnects(&self) -> u64 {
+        self.reconnects.load(Ordering::SeqCst)
+    }
+
+    pub fn last_success(&self) -> Option<i64> {
+        Some(self.last_success.load(Ordering::SeqCst)).filter(|millis| *millis > 0)
+    }
+
+    pub fn errors(&self) -> u64 {
+        self.errors.load(Ordering::SeqCst)
+    }
+
+    pub fn set(&self, state: ConnectionState) {
+        let previous = ConnectionState::from_u8(self.state.swap(state as u8, Ordering::SeqCst));
+        if previous == state {
+            return;
+        }
+        if previous == ConnectionState::Disconnected && state == ConnectionState::Connected {
+            self.reconnects.fetch_add(1, Ordering::SeqCst);
+        }
+        info!(
+            connection = self.name,
+            ?previous,
+            ?state,
+            reconnects = self.reconnects.load(Ordering::SeqCst),
+            "Connection state changed"
+        );
+    }
+
+    // Record a successful read or send, the connection is up
+    pub fn succeeded(&self) {
+        self.set(ConnectionState::Connected);
+        self.last_success.store(now_millis(), Ordering::SeqCst);
+    }
+
+    // Record a failed read or send, the connection counts as down until the next success
+    pub fn failed(&self) {
+        self.set(ConnectionState::Disconnected);
+        self.error();
+    }
+
+    // Record a failed request that leaves the connection up, e.g. an unknown signal
+    pub fn error(&self) {
+        self.errors.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct ConnectionHealth {
+    state: ConnectionState,
+    reconnects: u64,
+    // Last successful read or send in milliseconds since the Unix epoch
+    last_success: Option<i64>,
+    errors: u64,
+}
+
+impl ConnectionHealth {
+    fn new(connection: &Connection) -> Self {
+        ConnectionHealth {
+            state: connection.state(),
+            reconnects: connection.reconnects(),
+            last_success: connection.last_success(),
+            errors: connection.errors(),
+        }
+    }
+}
+
+// Health of a service, published periodically and returned by the HTTP probes
+#[derive(Serialize, Debug)]
+pub struct HealthMessage {
+    service: &'static str,
+    // All connections are up
+    ready: bool,
+    uptime_ms: u128,
+    // Milliseconds since the Unix epoch
+    timestamp: i64,
+    connections: BTreeMap<String, ConnectionHealth>,
+}
+
+impl HealthMessage {
+    pub fn new(service: &'static str, connections: &[&Connection], started: Instant) -> Self {
+        HealthMessage {
+            service,
+            ready: connections
+                .iter()
+                .all(|connection| connection.state() == ConnectionState::Connected),
+            uptime_ms: started.elapsed().as_millis(),
+            timestamp: now_millis(),
+            connections: connections
+                .iter()
+                .map(|connection| {
+                    (
+                        connection.name().to_lowercase(),
+                        ConnectionHealth::new(connection),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+// Container probes: /healthz while the process serves requests, /readyz while all connections are up
+pub fn probe(path: &str, health: &HealthMessage) -> Response {
+    match path {
+        "/healthz" => Response::json(200, health),
+        "/readyz" => Response::json(if health.ready { 200 } else { 503 }, health),
+        _ => Response::not_found(),
+    }
+}
+
+// Create the Zenoh transport with the given config, retried every interval until it succeeds
+pub async fn zenoh_transport(
+    authority: &str,
+    config: zenoh::Config,
+    retry: Duration,
+) -> UPTransportZenoh {
+    loop {
+        match UPTransportZenoh::builder(authority)
+            .expect("invalid authority name")
+            .with_config(config.clone())
+            .build()
+            .await
+        {
+            Ok(transport) => return transport,
+            Err(status) => {
+                warn!(?status, "Creating Zenoh transport failed");
+                tokio::time::sleep(retry).await;
+            }
+        }
+    }
+}
+
+// Publish the health message built by `health` over uProtocol every interval
+pub async fn publish_periodically<F>(
+    transport: &impl UTransport,
+    uri_provider: &impl LocalUriProvider,
+    interval: Duration,
+    health: F,
+) where
+    F: Fn() -> HealthMessage,
+{
+    let topic = uri_provider.get_resource_uri(RESOURCE_ID);
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        let message = serde_json::to_vec(&health())
+            .map_err(|err| err.to_string())
+            .and_then(|payload| {
+                UMessageBuilder::publish(topic.clone())
+                    .build_with_payload(payload, UPayloadFormat::UPAYLOAD_FORMAT_JSON)
+                    .map_err(|err| err.to_string())
+            });
+        match message {
+            Ok(message) => {
+                if let Err(status) = transport.send(message).await {
+                    warn!(?status, "Publishing health failed");
+                }
+            }
+            Err(err) => warn!(%err, "Building health message failed"),
+        }
+    }
+}
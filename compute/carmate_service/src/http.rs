@@ -0,0 +1,143 @@
+// Copyright (c) 2025 Eclipse Foundation and others.
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::timeout;
+use tracing::{info, warn};
+
+// Largest request head that is read, probes send a few hundred bytes
+const MAX_REQUEST_SIZE: usize = 8 * 1024;
+// Time a client gets to send the request head, so idle connections don't pile up
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Response of a local HTTP endpoint
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl Response {
+    pub fn json<T: Serialize>(status: u16, body: &T) -> Self {
+        match serde_json::to_string(body) {
+            Ok(body) => Response {
+                status,
+                content_type: "application/json",
+                body,
+            },
+            Err(err) => Response::text(500, format!("{err}\n")),
+        }
+    }
+
+    pub fn text(status: u16, body: String) -> Self {
+        Response {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body,
+        }
+    }
+
+    pub fn not_found() -> Self {
+        Response::text(404, "Not found\n".to_owned())
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "",
+    }
+}
+
+// Serve GET requests on all interfaces, e.g. for container probes. Only the request
+// line is evaluated and every connection is closed after one response.
+pub async fn serve<F>(port: u16, handler: F) -> std::io::Result<()>
+where
+    F: Fn(&str) -> Response + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind(("0.0.0.0", port)).await?;
+    info!(port, "Serving HTTP");
+    let handler = Arc::new(handler);
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                let handler = handler.clone();
+                tokio::spawn(async move {
+                    if let Err(err) = answer(stream, handler.as_ref()).await {
+                        warn!(?err, "Answering HTTP request failed");
+                    }
+                });
+            }
+            Err(err) => warn!(?err, "Accepting HTTP connection failed"),
+        }
+    }
+}
+
+async fn answer<F: Fn(&str) -> Response>(
+    mut stream: TcpStream,
+    handler: &F,
+) -> std::io::Result<()> {
+    let mut request = Vec::new();
+    let read_head = async {
+        let mut buffer = [0u8; 1024];
+        while !request.windows(4).any(|window| window == b"\r\n\r\n")
+            && request.len() < MAX_REQUEST_SIZE
+        {
+            let read = stream.read(&mut buffer).await?;
+            if read == 0 {
+                break;
+            }
+            request.extend_from_slice(&buffer[..read]);
+        }
+        Ok::<_, std::io::Error>(())
+    };
+    // Dropping the stream on expiry closes the connection
+    timeout(READ_TIMEOUT, read_head).await.map_err(|_| {
+        std::io::Error::new(
+            std::io::ErrorKind::TimedOut,
+            "Reading HTTP request timed out",
+        )
+    })??;
+
+    let head = String::from_utf8_lossy(&request);
+    let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+    let response = match (request_line.next(), request_line.next()) {
+        (Some("GET"), Some(target)) => handler(target.split('?').next().unwrap_or_default()),
+        _ => Response::text(405, "Only GET is supported\n".to_owned()),
+    };
+
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        reason(response.status),
+        response.content_type,
+        response.body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(response.body.as_bytes()).await?;
+    stream.shutdown().await
+}
@@ -0,0 +1,233 @@
+// Copyright (c) 2025 Eclipse Foundation and others.
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::health::Connection;
+use crate::http::Response;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Mutex, PoisonError};
+
+// Content type of the Prometheus text exposition format
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+// Upper bounds of the latency histogram buckets in seconds
+const LATENCY_BUCKETS: [f64; 12] = [
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0,
+];
+
+// Value of one metric in a family, written in the exposition format
+pub trait Metric: Default {
+    const KIND: &'static str;
+
+    // `labels` are the formatted label pairs, e.g. `topic="a",reason="b"`
+    fn write(&self, out: &mut String, name: &str, labels: &str);
+}
+
+impl Metric for u64 {
+    const KIND: &'static str = "counter";
+
+    fn write(&self, out: &mut String, name: &str, labels: &str) {
+        let _ = writeln!(out, "{name}{{{labels}}} {self}");
+    }
+}
+
+#[derive(Default)]
+pub struct Histogram {
+    // Observations per bucket, cumulative as in the exposition format
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        for (bound, bucket) in LATENCY_BUCKETS.iter().zip(&mut self.buckets) {
+            if value <= *bound {
+                *bucket += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += value;
+    }
+}
+
+impl Metric for Histogram {
+    const KIND: &'static str = "histogram";
+
+    fn write(&self, out: &mut String, name: &str, labels: &str) {
+        let prefix = if labels.is_empty() {
+            String::new()
+        } else {
+            format!("{labels},")
+        };
+        for (bound, bucket) in LATENCY_BUCKETS.iter().zip(self.buckets) {
+            let _ = writeln!(out, "{name}_bucket{{{prefix}le=\"{bound}\"}} {bucket}");
+        }
+        let _ = writeln!(out, "{name}_bucket{{{prefix}le=\"+Inf\"}} {}", self.count);
+        let _ = writeln!(out, "{name}_sum{{{labels}}} {}", self.sum);
+        let _ = writeln!(out, "{name}_count{{{labels}}} {}", self.count);
+    }
+}
+
+// Metrics of one name by label values, e.g. received messages by topic
+pub struct Family<M> {
+    name: &'static str,
+    help: &'static str,
+    labels: &'static [&'static str],
+    metrics: Mutex<BTreeMap<Vec<String>, M>>,
+}
+
+pub type Counters = Family<u64>;
+pub type Histograms = Family<Histogram>;
+
+impl<M: Metric> Family<M> {
+    pub const fn new(
+        name: &'static str,
+        help: &'static str,
+        labels: &'static [&'static str],
+    ) -> Self {
+        Family {
+            name,
+            help,
+            labels,
+            metrics: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    // Update the metric of the label values, given in the order of the label names
+    fn update(&self, values: &[&str], update: impl FnOnce(&mut M)) {
+        let key = values.iter().map(|value| (*value).to_owned()).collect();
+        // A panic while counting leaves consistent numbers, so a poisoned lock is used anyway
+        let mut metrics = self.metrics.lock().unwrap_or_else(PoisonError::into_inner);
+        update(metrics.entry(key).or_default());
+    }
+
+    pub fn render(&self, out: &mut String) {
+        write_header(out, self.name, M::KIND, self.help);
+        let metrics = self.metrics.lock().unwrap_or_else(PoisonError::into_inner);
+        for (values, metric) in metrics.iter() {
+            let labels = self
+                .labels
+                .iter()
+                .zip(values)
+                .map(|(label, value)| format!("{label}=\"{}\"", escape(value)))
+                .collect::<Vec<_>>()
+                .join(",");
+            metric.write(out, self.name, &labels);
+        }
+    }
+}
+
+impl Counters {
+    pub fn increment(&self, values: &[&str]) {
+        self.update(values, |count| *count += 1);
+    }
+}
+
+impl Histograms {
+    pub fn observe(&self, values: &[&str], value: f64) {
+        self.update(values, |histogram| histogram.observe(value));
+    }
+}
+
+// Reconnects of each connection, read from the health state when rendering
+pub fn render_reconnects(out: &mut String, name: &str, connections: &[&Connection]) {
+    write_header(
+        out,
+        name,
+        u64::KIND,
+        "Reconnects after a connection was lost",
+    );
+    for connection in connections {
+        let labels = format!(
+            "connection=\"{}\"",
+            escape(&connection.name().to_lowercase())
+        );
+        connection.reconnects().write(out, name, &labels);
+    }
+}
+
+// The /metrics endpoint with the rendered families
+pub fn response(body: String) -> Response {
+    Response {
+        status: 200,
+        content_type: CONTENT_TYPE,
+        body,
+    }
+}
+
+fn write_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+// Label values escape backslash, double quote and line feed
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counters_by_label_values() {
+        let counters = Counters::new("test_total", "Test", &["topic", "reason"]);
+        counters.increment(&["a", "invalid"]);
+        counters.increment(&["a", "invalid"]);
+        counters.increment(&["b\"\\\n", "invalid"]);
+        let mut out = String::new();
+        counters.render(&mut out);
+        assert_eq!(
+            out,
+            "# HELP test_total Test\n\
+             # TYPE test_total counter\n\
+             test_total{topic=\"a\",reason=\"invalid\"} 2\n\
+             test_total{topic=\"b\\\"\\\\\\n\",reason=\"invalid\"} 1\n"
+        );
+    }
+
+    #[test]
+    fn histogram_buckets_are_cumulative() {
+        let histograms = Histograms::new("test_seconds", "Test", &["signal"]);
+        histograms.observe(&["a"], 0.003);
+        histograms.observe(&["a"], 10.0);
+        let mut out = String::new();
+        histograms.render(&mut out);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[1], "# TYPE test_seconds histogram");
+        assert_eq!(lines[2], "test_seconds_bucket{signal=\"a\",le=\"0.001\"} 0");
+        assert_eq!(lines[4], "test_seconds_bucket{signal=\"a\",le=\"0.005\"} 1");
+        assert_eq!(lines[13], "test_seconds_bucket{signal=\"a\",le=\"5\"} 1");
+        assert_eq!(lines[14], "test_seconds_bucket{signal=\"a\",le=\"+Inf\"} 2");
+        assert_eq!(lines[15], "test_seconds_sum{signal=\"a\"} 10.003");
+        assert_eq!(lines[16], "test_seconds_count{signal=\"a\"} 2");
+    }
+
+    #[test]
+    fn reconnects_by_connection() {
+        let connection = Connection::new("MQTT");
+        let mut out = String::new();
+        render_reconnects(&mut out, "test_reconnects_total", &[&connection]);
+        assert!(out.ends_with("test_reconnects_total{connection=\"mqtt\"} 0\n"));
+    }
+}
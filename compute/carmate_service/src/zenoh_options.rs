@@ -0,0 +1,196 @@
+// Copyright (c) 2025 Eclipse Foundation and others.
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::Deserialize;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use zenoh::Config;
+
+// Default port of a Zenoh router
+const ROUTER_PORT: u16 = 7447;
+
+#[derive(clap::ValueEnum, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ZenohMode {
+    Peer,
+    Client,
+    Router,
+}
+
+impl ZenohMode {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ZenohMode::Peer => "peer",
+            ZenohMode::Client => "client",
+            ZenohMode::Router => "router",
+        }
+    }
+}
+
+// Zenoh settings of a uProtocol service: an optional JSON5 config file,
+// e.g. the zenoh.json shipped with the Python services, plus overrides on top
+#[derive(Debug, Default, Clone)]
+pub struct ZenohOptions {
+    pub config_file: Option<PathBuf>,
+    pub mode: Option<ZenohMode>,
+    // Router address, either "host" (tcp on port 7447) or a full endpoint like "tcp/host:7447"
+    pub router: Option<String>,
+    pub connect: Vec<String>,
+    pub listen: Vec<String>,
+    pub tls_root_ca: Option<PathBuf>,
+    pub tls_certificate: Option<PathBuf>,
+    pub tls_private_key: Option<PathBuf>,
+}
+
+// Zenoh flags of a service without a config file of its own, each one can also be set
+// by an environment variable
+#[derive(clap::Args, Debug)]
+pub struct ZenohArgs {
+    /// Zenoh configuration file (JSON5), the Zenoh flags below override its settings
+    #[clap(long, env = "ZENOH_CONFIG")]
+    zenoh_config: Option<PathBuf>,
+    /// Zenoh mode
+    #[clap(long, value_enum, env = "ZENOH_MODE")]
+    zenoh_mode: Option<ZenohMode>,
+    /// Zenoh router to connect to, "host" (tcp port 7447) or a full endpoint like "tls/host:7447"
+    #[clap(long, env = "ZENOH_ROUTER")]
+    router: Option<String>,
+    /// Zenoh endpoints to connect to, comma separated
+    #[clap(long, env = "ZENOH_CONNECT", value_delimiter = ',')]
+    connect: Vec<String>,
+    /// Zenoh endpoints to listen on, comma separated
+    #[clap(long, env = "ZENOH_LISTEN", value_delimiter = ',')]
+    listen: Vec<String>,
+    /// Root CA certificate for Zenoh TLS links
+    #[clap(long, env = "ZENOH_TLS_ROOT_CA")]
+    tls_root_ca: Option<PathBuf>,
+    /// Certificate for Zenoh TLS links (mutual TLS)
+    #[clap(long, env = "ZENOH_TLS_CERTIFICATE")]
+    tls_certificate: Option<PathBuf>,
+    /// Private key for Zenoh TLS links (mutual TLS)
+    #[clap(long, env = "ZENOH_TLS_PRIVATE_KEY")]
+    tls_private_key: Option<PathBuf>,
+}
+
+impl From<ZenohArgs> for ZenohOptions {
+    fn from(args: ZenohArgs) -> Self {
+        ZenohOptions {
+            config_file: args.zenoh_config,
+            mode: args.zenoh_mode,
+            router: args.router,
+            connect: args.connect,
+            listen: args.listen,
+            tls_root_ca: args.tls_root_ca,
+            tls_certificate: args.tls_certificate,
+            tls_private_key: args.tls_private_key,
+        }
+    }
+}
+
+fn router_endpoint(router: &str) -> String {
+    if router.contains('/') {
+        router.to_owned()
+    } else {
+        format!("tcp/{router}:{ROUTER_PORT}")
+    }
+}
+
+fn insert<T: Serialize + ?Sized>(config: &mut Config, key: &str, value: &T) -> Result<(), String> {
+    let json = serde_json::to_string(value).map_err(|err| err.to_string())?;
+    config
+        .insert_json5(key, &json)
+        .map_err(|err| format!("Invalid Zenoh setting {key} = {json}: {err}"))
+}
+
+fn existing_file(path: &Path) -> Result<String, String> {
+    if !path.is_file() {
+        return Err(format!("File {} does not exist", path.display()));
+    }
+    Ok(path.display().to_string())
+}
+
+impl ZenohOptions {
+    // Build the Zenoh config from the config file (or the Zenoh defaults) with the overrides applied
+    pub fn build(&self) -> Result<Config, String> {
+        let mut config = match &self.config_file {
+            Some(path) => Config::from_file(path)
+                .map_err(|err| format!("Failed to load Zenoh config {}: {err}", path.display()))?,
+            None => Config::default(),
+        };
+
+        if let Some(mode) = self.mode {
+            insert(&mut config, "mode", mode.as_str())?;
+        }
+
+        let mut connect = self.connect.clone();
+        if let Some(router) = &self.router {
+            connect.push(router_endpoint(router));
+        }
+        if !connect.is_empty() {
+            insert(&mut config, "connect/endpoints", &connect)?;
+        }
+        if !self.listen.is_empty() {
+            insert(&mut config, "listen/endpoints", &self.listen)?;
+        }
+
+        if let Some(root_ca) = &self.tls_root_ca {
+            insert(
+                &mut config,
+                "transport/link/tls/root_ca_certificate",
+                &existing_file(root_ca)?,
+            )?;
+        }
+        match (&self.tls_certificate, &self.tls_private_key) {
+            (Some(certificate), Some(private_key)) => {
+                let certificate = existing_file(certificate)?;
+                let private_key = existing_file(private_key)?;
+                // The same key pair is used for listening and for mutual TLS on connect
+                insert(
+                    &mut config,
+                    "transport/link/tls/listen_certificate",
+                    &certificate,
+                )?;
+                insert(
+                    &mut config,
+                    "transport/link/tls/listen_private_key",
+                    &private_key,
+                )?;
+                insert(
+                    &mut config,
+                    "transport/link/tls/connect_certificate",
+                    &certificate,
+                )?;
+                insert(
+                    &mut config,
+                    "transport/link/tls/connect_private_key",
+                    &private_key,
+                )?;
+                insert(&mut config, "transport/link/tls/enable_mtls", &true)?;
+            }
+            (None, None) => {}
+            _ => {
+                return Err(
+                    "TLS certificate and private key must be configured together".to_owned(),
+                );
+            }
+        }
+
+        Ok(config)
+    }
+}
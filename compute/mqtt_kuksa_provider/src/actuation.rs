@@ -0,0 +1,340 @@
+// Copyright (c) 2025 Eclipse Foundation and others.
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::health::{ConnectionState, Health};
+use crate::metrics::Metrics;
+use kuksa_rust_sdk::v2_proto;
+use kuksa_rust_sdk::v2_proto::open_provider_stream_request::Action as StreamRequest;
+use kuksa_rust_sdk::v2_proto::open_provider_stream_response::Action as StreamResponse;
+use kuksa_rust_sdk::v2_proto::signal_id::Signal;
+use kuksa_rust_sdk::v2_proto::val_client::ValClient;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::Status;
+use tonic::transport::Channel;
+use tracing::{debug, info, warn};
+
+// Delay before the provider stream is opened again after it failed
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+const CHANNEL_SIZE: usize = 100;
+
+// Request of the databroker to set an actuator to a new target value
+#[derive(Debug)]
+pub(crate) struct Request {
+    pub path: String,
+    pub value: Option<v2_proto::Value>,
+    // Correlation id of the request, logged with every event about it
+    pub correlation_id: String,
+    signal_id: v2_proto::SignalId,
+}
+
+// Why an actuation failed, reported back to the databroker
+#[derive(Debug)]
+pub(crate) struct Failure {
+    code: v2_proto::ErrorCode,
+    message: String,
+}
+
+impl Failure {
+    // The target value cannot be forwarded to the MCU
+    pub(crate) fn invalid(message: impl Into<String>) -> Self {
+        Failure {
+            code: v2_proto::ErrorCode::InvalidArgument,
+            message: message.into(),
+        }
+    }
+
+    // The MCU did not take the target value. The protocol has no code for an
+    // unreachable device, the message tells why.
+    pub(crate) fn unavailable(message: impl Into<String>) -> Self {
+        Failure {
+            code: v2_proto::ErrorCode::Unspecified,
+            message: message.into(),
+        }
+    }
+}
+
+// Actuation forwarded to the MCU, waiting for its confirmation
+struct Pending {
+    request: Request,
+    deadline: Instant,
+}
+
+// Actuation provider for the outbound routes: receives the actuation requests of the
+// databroker and acknowledges or fails them once the MCU confirmed or did not
+pub(crate) struct Provider {
+    requests: mpsc::Receiver<Request>,
+    responses: mpsc::Sender<v2_proto::BatchActuateStreamResponse>,
+    // By VSS path, at most one actuation per actuator
+    pending: HashMap<String, Pending>,
+}
+
+impl Provider {
+    // Register as provider of the given actuators, the provider stream is reopened whenever it fails
+    pub(crate) fn start(
+        host: &str,
+        paths: Vec<String>,
+        health: Arc<Health>,
+        metrics: Arc<Metrics>,
+    ) -> Self {
+        let (requests_tx, requests) = mpsc::channel(CHANNEL_SIZE);
+        let (responses, responses_rx) = mpsc::channel(CHANNEL_SIZE);
+        tokio::spawn(provide(
+            host.to_owned(),
+            paths,
+            health,
+            metrics,
+            requests_tx,
+            responses_rx,
+        ));
+        Provider {
+            requests,
+            responses,
+            pending: HashMap::new(),
+        }
+    }
+
+    // Next actuation request of the databroker
+    pub(crate) async fn next(&mut self) -> Option<Request> {
+        self.requests.recv().await
+    }
+
+    // Acknowledge or fail an actuation request
+    pub(crate) async fn respond(&self, request: &Request, result: Result<(), Failure>) {
+        let error = match result {
+            Ok(()) => v2_proto::Error {
+                code: v2_proto::ErrorCode::Ok as i32,
+                message: String::new(),
+            },
+            Err(failure) => {
+                warn!(
+                    path = %request.path,
+                    correlation_id = %request.correlation_id,
+                    message = %failure.message,
+                    "Actuation failed"
+                );
+                v2_proto::Error {
+                    code: failure.code as i32,
+                    message: failure.message,
+                }
+            }
+        };
+        let response = v2_proto::BatchActuateStreamResponse {
+            signal_id: Some(request.signal_id.clone()),
+            error: Some(error),
+        };
+        if self.responses.send(response).await.is_err() {
+            warn!(path = %request.path, "Provider stream task stopped, response dropped");
+        }
+    }
+
+    // Keep an actuation until the MCU confirms it, an older one of the same actuator is failed
+    pub(crate) async fn wait_for_confirmation(&mut self, request: Request, timeout: Duration) {
+        let pending = Pending {
+            deadline: Instant::now() + timeout,
+            request,
+        };
+        if let Some(superseded) = self.pending.insert(pending.request.path.clone(), pending) {
+            self.respond(
+                &superseded.request,
+                Err(Failure::unavailable(
+                    "Superseded by a newer actuation request",
+                )),
+            )
+            .await;
+        }
+    }
+
+    // Actuation of the actuator that waits for a confirmation
+    pub(crate) fn take_pending(&mut self, path: &str) -> Option<Request> {
+        self.pending.remove(path).map(|pending| pending.request)
+    }
+
+    // Fail the actuations the MCU did not confirm in time
+    pub(crate) async fn expire(&mut self) {
+        let now = Instant::now();
+        let expired: Vec<String> = self
+            .pending
+            .iter()
+            .filter(|(_, pending)| pending.deadline <= now)
+            .map(|(path, _)| path.clone())
+            .collect();
+        for path in expired {
+            if let Some(request) = self.take_pending(&path) {
+                self.respond(
+                    &request,
+                    Err(Failure::unavailable(
+                        "The MCU did not confirm the actuation",
+                    )),
+                )
+                .await;
+            }
+        }
+    }
+}
+
+// Keep the provider stream open until the provider is dropped
+async fn provide(
+    host: String,
+    paths: Vec<String>,
+    health: Arc<Health>,
+    metrics: Arc<Metrics>,
+    requests: mpsc::Sender<Request>,
+    mut responses: mpsc::Receiver<v2_proto::BatchActuateStreamResponse>,
+) {
+    if paths.is_empty() {
+        return;
+    }
+    loop {
+        match serve(&host, &paths, &health, &requests, &mut responses).await {
+            Ok(()) => return,
+            Err(status) => {
+                warn!(?status, "Provider stream failed");
+                health.kuksa.failed();
+                metrics.grpc_status(status.code());
+            }
+        }
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+fn path_of(signal_id: &v2_proto::SignalId, paths_by_id: &HashMap<i32, String>) -> Option<String> {
+    match &signal_id.signal {
+        Some(Signal::Path(path)) => Some(path.clone()),
+        Some(Signal::Id(id)) => paths_by_id.get(id).cloned(),
+        None => None,
+    }
+}
+
+// Register the actuators and relay requests and responses until the stream fails,
+// returns Ok once the provider is dropped
+async fn serve(
+    host: &str,
+    paths: &[String],
+    health: &Health,
+    requests: &mpsc::Sender<Request>,
+    responses: &mut mpsc::Receiver<v2_proto::BatchActuateStreamResponse>,
+) -> Result<(), Status> {
+    let channel = Channel::from_shared(host.to_owned())
+        .map_err(|err| Status::invalid_argument(err.to_string()))?
+        .connect()
+        .await
+        .map_err(|err| Status::unavailable(err.to_string()))?;
+    let mut client = ValClient::new(channel);
+
+    // Actuation requests may identify the actuator by its id instead of its path
+    let mut paths_by_id = HashMap::new();
+    for path in paths {
+        let metadata = client
+            .list_metadata(v2_proto::ListMetadataRequest {
+                root: path.clone(),
+                filter: String::new(),
+            })
+            .await?
+            .into_inner()
+            .metadata;
+        for entry in metadata.into_iter().filter(|entry| &entry.path == path) {
+            paths_by_id.insert(entry.id, entry.path);
+        }
+    }
+
+    let (stream_tx, stream_rx) = mpsc::channel(CHANNEL_SIZE);
+    let closed = || Status::unavailable("Provider stream closed");
+    stream_tx
+        .send(v2_proto::OpenProviderStreamRequest {
+            action: Some(StreamRequest::ProvideActuationRequest(
+                v2_proto::ProvideActuationRequest {
+                    actuator_identifiers: paths
+                        .iter()
+                        .map(|path| v2_proto::SignalId {
+                            signal: Some(Signal::Path(path.clone())),
+                        })
+                        .collect(),
+                },
+            )),
+        })
+        .await
+        .map_err(|_| closed())?;
+    let mut stream = client
+        .open_provider_stream(ReceiverStream::new(stream_rx))
+        .await?
+        .into_inner();
+
+    loop {
+        tokio::select! {
+            message = stream.message() => {
+                let Some(message) = message? else {
+                    return Err(closed());
+                };
+                match message.action {
+                    Some(StreamResponse::ProvideActuationResponse(_)) => {
+                        health.kuksa.set(ConnectionState::Connected);
+                        info!(actuators = ?paths, "Registered as actuation provider");
+                    }
+                    Some(StreamResponse::BatchActuateStreamRequest(batch)) => {
+                        for actuate in batch.actuate_requests {
+                            let signal_id = actuate.signal_id.unwrap_or_default();
+                            let Some(path) = path_of(&signal_id, &paths_by_id) else {
+                                warn!(?signal_id, "Actuation request for an unknown actuator");
+                                let response = v2_proto::BatchActuateStreamResponse {
+                                    signal_id: Some(signal_id),
+                                    error: Some(v2_proto::Error {
+                                        code: v2_proto::ErrorCode::NotFound as i32,
+                                        message: "Actuator is not provided by this provider".to_owned(),
+                                    }),
+                                };
+                                stream_tx
+                                    .send(v2_proto::OpenProviderStreamRequest {
+                                        action: Some(StreamRequest::BatchActuateStreamResponse(response)),
+                                    })
+                                    .await
+                                    .map_err(|_| closed())?;
+                                continue;
+                            };
+                            let request = Request {
+                                path,
+                                value: actuate.value,
+                                correlation_id: crate::correlation_id(),
+                                signal_id,
+                            };
+                            if requests.send(request).await.is_err() {
+                                return Ok(());
+                            }
+                        }
+                    }
+                    other => debug!(?other, "Ignoring provider stream message"),
+                }
+            }
+            response = responses.recv() => {
+                let Some(response) = response else {
+                    return Ok(());
+                };
+                stream_tx
+                    .send(v2_proto::OpenProviderStreamRequest {
+                        action: Some(StreamRequest::BatchActuateStreamResponse(response)),
+                    })
+                    .await
+                    .map_err(|_| closed())?;
+            }
+        }
+    }
+}
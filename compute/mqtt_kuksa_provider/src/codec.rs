@@ -0,0 +1,1282 @@
+// Copyright (c) 2025 Eclipse Foundation and others.
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::DisplayDatapoint;
+use crate::color::{self, Rgb};
+use crate::mapping::{Codec, DataType};
+use kuksa_rust_sdk::v2_proto;
+use kuksa_rust_sdk::v2_proto::value::TypedValue;
+use serde::Deserialize;
+use serde_json::Value as Json;
+use std::fmt;
+use std::str::Utf8Error;
+
+// Why an MQTT payload is not a valid value of the datatype of its route
+#[derive(Debug)]
+pub(crate) enum DecodeError {
+    // Text payload that is not UTF-8
+    NotUtf8(Utf8Error),
+    // Payload that is not a well-formed document of its codec
+    Malformed {
+        format: &'static str,
+        reason: String,
+    },
+    // Document without a value at the location of the selector
+    NotFound {
+        selector: String,
+    },
+    // Value of another type or out of the range of the datatype
+    InvalidValue {
+        value: String,
+        datatype: DataType,
+    },
+    // Binary payload whose length does not fit the datatype
+    InvalidLength {
+        length: usize,
+        size: usize,
+        datatype: DataType,
+    },
+    // Datatype without an encoding in the codec
+    Unsupported {
+        datatype: DataType,
+    },
+}
+
+impl DecodeError {
+    // Short machine readable reason, e.g. for metric labels
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            DecodeError::NotUtf8(_) => "not-utf8",
+            DecodeError::Malformed { .. } => "malformed",
+            DecodeError::NotFound { .. } => "not-found",
+            DecodeError::InvalidValue { .. } => "invalid-value",
+            DecodeError::InvalidLength { .. } => "invalid-length",
+            DecodeError::Unsupported { .. } => "unsupported",
+        }
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::NotUtf8(err) => write!(f, "Payload is not UTF-8: {err}"),
+            DecodeError::Malformed { format, reason } => {
+                write!(f, "Payload is not valid {format}: {reason}")
+            }
+            DecodeError::NotFound { selector } => {
+                write!(f, "Selector {selector} matches nothing in the payload")
+            }
+            DecodeError::InvalidValue { value, datatype } => {
+                write!(f, "{value} is not a valid {datatype}")
+            }
+            DecodeError::InvalidLength {
+                length,
+                size,
+                datatype,
+            } => match datatype.element() {
+                Some(_) => write!(
+                    f,
+                    "{length} bytes are not a valid {datatype}, expected a multiple of {size}"
+                ),
+                None => write!(
+                    f,
+                    "{length} bytes are not a valid {datatype}, expected {size}"
+                ),
+            },
+            DecodeError::Unsupported { datatype } => {
+                write!(f, "{datatype} has no encoding in this codec")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+fn malformed(format: &'static str, err: impl fmt::Display) -> DecodeError {
+    DecodeError::Malformed {
+        format,
+        reason: err.to_string(),
+    }
+}
+
+// Location of a value in a document, a subset of JSONPath:
+// "$" followed by ".field", "['field']" or "[index]" steps
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(try_from = "String")]
+pub(crate) struct Selector {
+    text: String,
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Step {
+    Field(String),
+    Index(usize),
+}
+
+impl TryFrom<String> for Selector {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        let invalid =
+            || format!("Invalid selector {text:?}, expected e.g. \"$.temp\" or \"$.values[0]\"");
+        let mut rest = text.strip_prefix('$').ok_or_else(invalid)?;
+        let mut steps = Vec::new();
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix('.') {
+                let end = after.find(['.', '[']).unwrap_or(after.len());
+                if end == 0 {
+                    return Err(invalid());
+                }
+                steps.push(Step::Field(after[..end].to_owned()));
+                rest = &after[end..];
+            } else if let Some(after) = rest.strip_prefix('[') {
+                let end = after.find(']').ok_or_else(invalid)?;
+                let inner = &after[..end];
+                let step = match unquoted(inner) {
+                    Some(field) => Step::Field(field.to_owned()),
+                    None => Step::Index(inner.parse().map_err(|_| invalid())?),
+                };
+                steps.push(step);
+                rest = &after[end + 1..];
+            } else {
+                return Err(invalid());
+            }
+        }
+        Ok(Selector { text, steps })
+    }
+}
+
+impl Selector {
+    // Whether the selector consists of object fields only and can therefore wrap a value
+    pub(crate) fn is_fields(&self) -> bool {
+        self.steps.iter().all(|step| matches!(step, Step::Field(_)))
+    }
+
+    fn select<'a>(&self, document: &'a Json) -> Option<&'a Json> {
+        self.steps
+            .iter()
+            .try_fold(document, |value, step| match step {
+                Step::Field(name) => value.get(name),
+                Step::Index(index) => value.get(*index),
+            })
+    }
+
+    // Document with the value at the location of the selector
+    fn wrap(&self, value: Json) -> Json {
+        self.steps
+            .iter()
+            .rev()
+            .fold(value, |value, step| match step {
+                Step::Field(name) => {
+                    let mut object = serde_json::Map::new();
+                    object.insert(name.clone(), value);
+                    Json::Object(object)
+                }
+                // Outbound selectors are validated to contain fields only
+                Step::Index(_) => value,
+            })
+    }
+}
+
+impl fmt::Display for Selector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+// Decode an MQTT payload into a value of the given VSS datatype
+pub(crate) fn decode(
+    codec: Codec,
+    selector: Option<&Selector>,
+    datatype: DataType,
+    payload: &[u8],
+) -> Result<v2_proto::Value, DecodeError> {
+    let typed_value = match codec {
+        Codec::Text => {
+            let text = str::from_utf8(payload)
+                .map_err(DecodeError::NotUtf8)?
+                .trim();
+            // String arrays are encoded as JSON array, the elements may contain commas
+            if datatype == DataType::StringArray
+                && let Ok(values) = serde_json::from_str::<Vec<String>>(text)
+            {
+                TypedValue::StringArray(v2_proto::StringArray { values })
+            } else {
+                typed_value(datatype, &text)?
+            }
+        }
+        Codec::Json => {
+            let document: Json =
+                serde_json::from_slice(payload).map_err(|err| malformed("JSON", err))?;
+            selected_value(datatype, selector, &document)?
+        }
+        Codec::Cbor => {
+            let document: Json =
+                ciborium::from_reader(payload).map_err(|err| malformed("CBOR", err))?;
+            selected_value(datatype, selector, &document)?
+        }
+        Codec::Msgpack => {
+            let document: Json =
+                rmp_serde::from_slice(payload).map_err(|err| malformed("MessagePack", err))?;
+            selected_value(datatype, selector, &document)?
+        }
+        Codec::BinaryLe => decode_binary(datatype, payload, Endian::Little)?,
+        Codec::BinaryBe => decode_binary(datatype, payload, Endian::Big)?,
+        Codec::Rgb => {
+            let text = str::from_utf8(payload).map_err(DecodeError::NotUtf8)?;
+            let rgb = Rgb::from_payload(text).ok_or_else(|| DecodeError::InvalidValue {
+                value: text.to_owned(),
+                datatype,
+            })?;
+            TypedValue::String(rgb.to_string())
+        }
+    };
+    Ok(v2_proto::Value {
+        typed_value: Some(typed_value),
+    })
+}
+
+// Encode a value read from Kuksa into an MQTT payload
+pub(crate) fn encode(
+    codec: Codec,
+    selector: Option<&Selector>,
+    datatype: DataType,
+    value: v2_proto::Value,
+) -> Result<Vec<u8>, String> {
+    match codec {
+        Codec::Text => Ok(match &value.typed_value {
+            // Shortest text that reads back as the same number, not rounded for display
+            Some(TypedValue::Float(number)) => number.to_string(),
+            Some(TypedValue::Double(number)) => number.to_string(),
+            Some(TypedValue::StringArray(array)) => serde_json::to_string(&array.values)
+                .map_err(|err| format!("Encoding JSON failed: {err}"))?,
+            _ => DisplayDatapoint(value).to_string(),
+        }
+        .into_bytes()),
+        Codec::Json => serde_json::to_vec(&document(selector, &value))
+            .map_err(|err| format!("Encoding JSON failed: {err}")),
+        Codec::Cbor => {
+            let mut payload = Vec::new();
+            ciborium::into_writer(&document(selector, &value), &mut payload)
+                .map_err(|err| format!("Encoding CBOR failed: {err}"))?;
+            Ok(payload)
+        }
+        Codec::Msgpack => rmp_serde::to_vec(&document(selector, &value))
+            .map_err(|err| format!("Encoding MessagePack failed: {err}")),
+        Codec::BinaryLe => encode_binary(datatype, &value, Endian::Little),
+        Codec::BinaryBe => encode_binary(datatype, &value, Endian::Big),
+        Codec::Rgb => match &value.typed_value {
+            Some(TypedValue::String(text)) => Ok(color::parse(text)?.payload().into_bytes()),
+            other => Err(format!("{other:?} is not a color")),
+        },
+    }
+}
+
+// Whether a value read from Kuksa has the datatype of its route
+pub(crate) fn matches(datatype: DataType, value: &TypedValue) -> bool {
+    matches!(
+        (datatype, value),
+        (DataType::String, TypedValue::String(_))
+            | (DataType::Boolean, TypedValue::Bool(_))
+            | (
+                DataType::Int8 | DataType::Int16 | DataType::Int32,
+                TypedValue::Int32(_)
+            )
+            | (DataType::Int64, TypedValue::Int64(_))
+            | (
+                DataType::Uint8 | DataType::Uint16 | DataType::Uint32,
+                TypedValue::Uint32(_)
+            )
+            | (DataType::Uint64, TypedValue::Uint64(_))
+            | (DataType::Float, TypedValue::Float(_))
+            | (DataType::Double, TypedValue::Double(_))
+            | (DataType::StringArray, TypedValue::StringArray(_))
+            | (DataType::BooleanArray, TypedValue::BoolArray(_))
+            | (
+                DataType::Int8Array | DataType::Int16Array | DataType::Int32Array,
+                TypedValue::Int32Array(_)
+            )
+            | (DataType::Int64Array, TypedValue::Int64Array(_))
+            | (
+                DataType::Uint8Array | DataType::Uint16Array | DataType::Uint32Array,
+                TypedValue::Uint32Array(_)
+            )
+            | (DataType::Uint64Array, TypedValue::Uint64Array(_))
+            | (DataType::FloatArray, TypedValue::FloatArray(_))
+            | (DataType::DoubleArray, TypedValue::DoubleArray(_))
+    )
+}
+
+// A decoded payload, or an element of it, that values of any datatype are read from
+trait Source: fmt::Display + Sized {
+    fn string(&self) -> Option<String>;
+    fn boolean(&self) -> Option<bool>;
+    fn signed(&self) -> Option<i64>;
+    fn unsigned(&self) -> Option<u64>;
+    fn real(&self) -> Option<f64>;
+    fn items(&self) -> Option<Vec<Self>>;
+}
+
+// Text payloads, arrays as comma separated list, optionally enclosed in brackets
+impl<'a> Source for &'a str {
+    fn string(&self) -> Option<String> {
+        Some(unquoted(self).unwrap_or(*self).to_owned())
+    }
+
+    fn boolean(&self) -> Option<bool> {
+        match *self {
+            "true" | "1" => Some(true),
+            "false" | "0" => Some(false),
+            _ => None,
+        }
+    }
+
+    fn signed(&self) -> Option<i64> {
+        self.parse().ok()
+    }
+
+    fn unsigned(&self) -> Option<u64> {
+        self.parse().ok()
+    }
+
+    fn real(&self) -> Option<f64> {
+        self.parse().ok()
+    }
+
+    fn items(&self) -> Option<Vec<&'a str>> {
+        let text: &'a str = self;
+        let list = text
+            .strip_prefix('[')
+            .and_then(|list| list.strip_suffix(']'))
+            .unwrap_or(text)
+            .trim();
+        if list.is_empty() {
+            return Some(Vec::new());
+        }
+        Some(list.split(',').map(str::trim).collect())
+    }
+}
+
+// Documents of the JSON, CBOR and MessagePack codecs
+impl<'a> Source for &'a Json {
+    fn string(&self) -> Option<String> {
+        self.as_str().map(str::to_owned)
+    }
+
+    fn boolean(&self) -> Option<bool> {
+        self.as_bool()
+    }
+
+    fn signed(&self) -> Option<i64> {
+        self.as_i64()
+    }
+
+    fn unsigned(&self) -> Option<u64> {
+        self.as_u64()
+    }
+
+    fn real(&self) -> Option<f64> {
+        self.as_f64()
+    }
+
+    fn items(&self) -> Option<Vec<&'a Json>> {
+        let document: &'a Json = self;
+        document.as_array().map(|items| items.iter().collect())
+    }
+}
+
+// Text enclosed in single or double quotes
+fn unquoted(text: &str) -> Option<&str> {
+    ['\'', '"'].into_iter().find_map(|quote| {
+        text.strip_prefix(quote)
+            .and_then(|text| text.strip_suffix(quote))
+    })
+}
+
+fn invalid<S: Source>(source: &S, datatype: DataType) -> DecodeError {
+    DecodeError::InvalidValue {
+        value: source.to_string(),
+        datatype,
+    }
+}
+
+fn string<S: Source>(source: &S, datatype: DataType) -> Result<String, DecodeError> {
+    source.string().ok_or_else(|| invalid(source, datatype))
+}
+
+fn boolean<S: Source>(source: &S, datatype: DataType) -> Result<bool, DecodeError> {
+    source.boolean().ok_or_else(|| invalid(source, datatype))
+}
+
+fn signed<T: TryFrom<i64>, S: Source>(source: &S, datatype: DataType) -> Result<T, DecodeError> {
+    source
+        .signed()
+        .and_then(|value| T::try_from(value).ok())
+        .ok_or_else(|| invalid(source, datatype))
+}
+
+fn unsigned<T: TryFrom<u64>, S: Source>(source: &S, datatype: DataType) -> Result<T, DecodeError> {
+    source
+        .unsigned()
+        .and_then(|value| T::try_from(value).ok())
+        .ok_or_else(|| invalid(source, datatype))
+}
+
+// NaN and infinity are rejected, e.g. "nan" from a failed sensor reading
+fn double<S: Source>(source: &S, datatype: DataType) -> Result<f64, DecodeError> {
+    source
+        .real()
+        .filter(|value| value.is_finite())
+        .ok_or_else(|| invalid(source, datatype))
+}
+
+fn float<S: Source>(source: &S, datatype: DataType) -> Result<f32, DecodeError> {
+    let value = double(source, datatype)? as f32;
+    // Values beyond the range of f32 turn into infinity
+    if !value.is_finite() {
+        return Err(invalid(source, datatype));
+    }
+    Ok(value)
+}
+
+fn list<T, S: Source>(
+    source: &S,
+    datatype: DataType,
+    read: impl Fn(&S, DataType) -> Result<T, DecodeError>,
+) -> Result<Vec<T>, DecodeError> {
+    // Array types always have an element type
+    let element = datatype.element().unwrap_or(datatype);
+    source
+        .items()
+        .ok_or_else(|| invalid(source, datatype))?
+        .iter()
+        .map(|item| read(item, element))
+        .collect()
+}
+
+fn typed_value<S: Source>(datatype: DataType, source: &S) -> Result<TypedValue, DecodeError> {
+    Ok(match datatype {
+        DataType::String => TypedValue::String(string(source, datatype)?),
+        DataType::Boolean => TypedValue::Bool(boolean(source, datatype)?),
+        DataType::Int8 => TypedValue::Int32(signed::<i8, _>(source, datatype)?.into()),
+        DataType::Int16 => TypedValue::Int32(signed::<i16, _>(source, datatype)?.into()),
+        DataType::Int32 => TypedValue::Int32(signed(source, datatype)?),
+        DataType::Int64 => TypedValue::Int64(signed(source, datatype)?),
+        DataType::Uint8 => TypedValue::Uint32(unsigned::<u8, _>(source, datatype)?.into()),
+        DataType::Uint16 => TypedValue::Uint32(unsigned::<u16, _>(source, datatype)?.into()),
+        DataType::Uint32 => TypedValue::Uint32(unsigned(source, datatype)?),
+        DataType::Uint64 => TypedValue::Uint64(unsigned(source, datatype)?),
+        DataType::Float => TypedValue::Float(float(source, datatype)?),
+        DataType::Double => TypedValue::Double(double(source, datatype)?),
+        DataType::StringArray => TypedValue::StringArray(v2_proto::StringArray {
+            values: list(source, datatype, string)?,
+        }),
+        DataType::BooleanArray => TypedValue::BoolArray(v2_proto::BoolArray {
+            values: list(source, datatype, boolean)?,
+        }),
+        DataType::Int8Array => TypedValue::Int32Array(v2_proto::Int32Array {
+            values: list(source, datatype, |item, element| {
+                Ok(signed::<i8, _>(item, element)?.into())
+            })?,
+        }),
+        DataType::Int16Array => TypedValue::Int32Array(v2_proto::Int32Array {
+            values: list(source, datatype, |item, element| {
+                Ok(signed::<i16, _>(item, element)?.into())
+            })?,
+        }),
+        DataType::Int32Array => TypedValue::Int32Array(v2_proto::Int32Array {
+            values: list(source, datatype, signed)?,
+        }),
+        DataType::Int64Array => TypedValue::Int64Array(v2_proto::Int64Array {
+            values: list(source, datatype, signed)?,
+        }),
+        DataType::Uint8Array => TypedValue::Uint32Array(v2_proto::Uint32Array {
+            values: list(source, datatype, |item, element| {
+                Ok(unsigned::<u8, _>(item, element)?.into())
+            })?,
+        }),
+        DataType::Uint16Array => TypedValue::Uint32Array(v2_proto::Uint32Array {
+            values: list(source, datatype, |item, element| {
+                Ok(unsigned::<u16, _>(item, element)?.into())
+            })?,
+        }),
+        DataType::Uint32Array => TypedValue::Uint32Array(v2_proto::Uint32Array {
+            values: list(source, datatype, unsigned)?,
+        }),
+        DataType::Uint64Array => TypedValue::Uint64Array(v2_proto::Uint64Array {
+            values: list(source, datatype, unsigned)?,
+        }),
+        DataType::FloatArray => TypedValue::FloatArray(v2_proto::FloatArray {
+            values: list(source, datatype, float)?,
+        }),
+        DataType::DoubleArray => TypedValue::DoubleArray(v2_proto::DoubleArray {
+            values: list(source, datatype, double)?,
+        }),
+    })
+}
+
+fn selected_value(
+    datatype: DataType,
+    selector: Option<&Selector>,
+    document: &Json,
+) -> Result<TypedValue, DecodeError> {
+    let value = match selector {
+        Some(selector) => selector
+            .select(document)
+            .ok_or_else(|| DecodeError::NotFound {
+                selector: selector.to_string(),
+            })?,
+        None => document,
+    };
+    typed_value(datatype, &value)
+}
+
+// Document of a value read from Kuksa, wrapped in the fields of the selector
+fn document(selector: Option<&Selector>, value: &v2_proto::Value) -> Json {
+    let value = match &value.typed_value {
+        Some(TypedValue::String(value)) => Json::from(value.as_str()),
+        Some(TypedValue::Bool(value)) => Json::from(*value),
+        Some(TypedValue::Int32(value)) => Json::from(*value),
+        Some(TypedValue::Int64(value)) => Json::from(*value),
+        Some(TypedValue::Uint32(value)) => Json::from(*value),
+        Some(TypedValue::Uint64(value)) => Json::from(*value),
+        Some(TypedValue::Float(value)) => Json::from(*value),
+        Some(TypedValue::Double(value)) => Json::from(*value),
+        Some(TypedValue::StringArray(array)) => Json::from(array.values.clone()),
+        Some(TypedValue::BoolArray(array)) => Json::from(array.values.clone()),
+        Some(TypedValue::Int32Array(array)) => Json::from(array.values.clone()),
+        Some(TypedValue::Int64Array(array)) => Json::from(array.values.clone()),
+        Some(TypedValue::Uint32Array(array)) => Json::from(array.values.clone()),
+        Some(TypedValue::Uint64Array(array)) => Json::from(array.values.clone()),
+        Some(TypedValue::FloatArray(array)) => Json::from(array.values.clone()),
+        Some(TypedValue::DoubleArray(array)) => Json::from(array.values.clone()),
+        None => Json::Null,
+    };
+    match selector {
+        Some(selector) => selector.wrap(value),
+        None => value,
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Endian {
+    Little,
+    Big,
+}
+
+// Fixed-size number of the binary codecs
+trait Number: Sized + Copy {
+    const SIZE: usize;
+    fn read(bytes: &[u8], endian: Endian) -> Self;
+    fn write(self, endian: Endian, out: &mut Vec<u8>);
+}
+
+macro_rules! number {
+    ($($type:ty),*) => {$(
+        impl Number for $type {
+            const SIZE: usize = size_of::<$type>();
+
+            // Callers pass exactly SIZE bytes
+            fn read(bytes: &[u8], endian: Endian) -> Self {
+                let bytes = bytes.try_into().unwrap_or_default();
+                match endian {
+                    Endian::Little => <$type>::from_le_bytes(bytes),
+                    Endian::Big => <$type>::from_be_bytes(bytes),
+                }
+            }
+
+            fn write(self, endian: Endian, out: &mut Vec<u8>) {
+                match endian {
+                    Endian::Little => out.extend_from_slice(&self.to_le_bytes()),
+                    Endian::Big => out.extend_from_slice(&self.to_be_bytes()),
+                }
+            }
+        }
+    )*};
+}
+
+number!(u8, i8, i16, i32, i64, u16, u32, u64, f32, f64);
+
+fn read_one<T: Number>(
+    payload: &[u8],
+    endian: Endian,
+    datatype: DataType,
+) -> Result<T, DecodeError> {
+    if payload.len() != T::SIZE {
+        return Err(DecodeError::InvalidLength {
+            length: payload.len(),
+            size: T::SIZE,
+            datatype,
+        });
+    }
+    Ok(T::read(payload, endian))
+}
+
+fn read_all<T: Number>(
+    payload: &[u8],
+    endian: Endian,
+    datatype: DataType,
+) -> Result<Vec<T>, DecodeError> {
+    if !payload.len().is_multiple_of(T::SIZE) {
+        return Err(DecodeError::InvalidLength {
+            length: payload.len(),
+            size: T::SIZE,
+            datatype,
+        });
+    }
+    Ok(payload
+        .chunks_exact(T::SIZE)
+        .map(|chunk| T::read(chunk, endian))
+        .collect())
+}
+
+fn to_bool(byte: u8, datatype: DataType) -> Result<bool, DecodeError> {
+    match byte {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(DecodeError::InvalidValue {
+            value: format!("Byte {byte}"),
+            datatype,
+        }),
+    }
+}
+
+// NaN and infinity are rejected as in the text and document codecs
+fn finite<T>(value: T, datatype: DataType) -> Result<T, DecodeError>
+where
+    T: Copy + Into<f64> + fmt::Display,
+{
+    if value.into().is_finite() {
+        Ok(value)
+    } else {
+        Err(DecodeError::InvalidValue {
+            value: value.to_string(),
+            datatype,
+        })
+    }
+}
+
+fn decode_binary(
+    datatype: DataType,
+    payload: &[u8],
+    endian: Endian,
+) -> Result<TypedValue, DecodeError> {
+    Ok(match datatype {
+        DataType::String | DataType::StringArray => {
+            return Err(DecodeError::Unsupported { datatype });
+        }
+        DataType::Boolean => {
+            TypedValue::Bool(to_bool(read_one(payload, endian, datatype)?, datatype)?)
+        }
+        DataType::Int8 => TypedValue::Int32(read_one::<i8>(payload, endian, datatype)?.into()),
+        DataType::Int16 => TypedValue::Int32(read_one::<i16>(payload, endian, datatype)?.into()),
+        DataType::Int32 => TypedValue::Int32(read_one(payload, endian, datatype)?),
+        DataType::Int64 => TypedValue::Int64(read_one(payload, endian, datatype)?),
+        DataType::Uint8 => TypedValue::Uint32(read_one::<u8>(payload, endian, datatype)?.into()),
+        DataType::Uint16 => TypedValue::Uint32(read_one::<u16>(payload, endian, datatype)?.into()),
+        DataType::Uint32 => TypedValue::Uint32(read_one(payload, endian, datatype)?),
+        DataType::Uint64 => TypedValue::Uint64(read_one(payload, endian, datatype)?),
+        DataType::Float => {
+            TypedValue::Float(finite(read_one(payload, endian, datatype)?, datatype)?)
+        }
+        DataType::Double => {
+            TypedValue::Double(finite(read_one(payload, endian, datatype)?, datatype)?)
+        }
+        DataType::BooleanArray => TypedValue::BoolArray(v2_proto::BoolArray {
+            values: read_all::<u8>(payload, endian, datatype)?
+                .into_iter()
+                .map(|byte| to_bool(byte, datatype))
+                .collect::<Result<_, _>>()?,
+        }),
+        DataType::Int8Array => TypedValue::Int32Array(v2_proto::Int32Array {
+            values: read_all::<i8>(payload, endian, datatype)?
+                .into_iter()
+                .map(i32::from)
+                .collect(),
+        }),
+        DataType::Int16Array => TypedValue::Int32Array(v2_proto::Int32Array {
+            values: read_all::<i16>(payload, endian, datatype)?
+                .into_iter()
+                .map(i32::from)
+                .collect(),
+        }),
+        DataType::Int32Array => TypedValue::Int32Array(v2_proto::Int32Array {
+            values: read_all(payload, endian, datatype)?,
+        }),
+        DataType::Int64Array => TypedValue::Int64Array(v2_proto::Int64Array {
+            values: read_all(payload, endian, datatype)?,
+        }),
+        DataType::Uint8Array => TypedValue::Uint32Array(v2_proto::Uint32Array {
+            values: read_all::<u8>(payload, endian, datatype)?
+                .into_iter()
+                .map(u32::from)
+                .collect(),
+        }),
+        DataType::Uint16Array => TypedValue::Uint32Array(v2_proto::Uint32Array {
+            values: read_all::<u16>(payload, endian, datatype)?
+                .into_iter()
+                .map(u32::from)
+                .collect(),
+        }),
+        DataType::Uint32Array => TypedValue::Uint32Array(v2_proto::Uint32Array {
+            values: read_all(payload, endian, datatype)?,
+        }),
+        DataType::Uint64Array => TypedValue::Uint64Array(v2_proto::Uint64Array {
+            values: read_all(payload, endian, datatype)?,
+        }),
+        DataType::FloatArray => TypedValue::FloatArray(v2_proto::FloatArray {
+            values: read_all::<f32>(payload, endian, datatype)?
+                .into_iter()
+                .map(|value| finite(value, datatype))
+                .collect::<Result<_, _>>()?,
+        }),
+        DataType::DoubleArray => TypedValue::DoubleArray(v2_proto::DoubleArray {
+            values: read_all::<f64>(payload, endian, datatype)?
+                .into_iter()
+                .map(|value| finite(value, datatype))
+                .collect::<Result<_, _>>()?,
+        }),
+    })
+}
+
+// Write values narrowed to the size of the datatype, e.g. int8 values of an Int32 array
+fn write_all<T, S>(
+    values: &[S],
+    endian: Endian,
+    datatype: DataType,
+    out: &mut Vec<u8>,
+) -> Result<(), String>
+where
+    T: Number + TryFrom<S>,
+    S: Copy + fmt::Display,
+{
+    for value in values {
+        T::try_from(*value)
+            .map_err(|_| format!("{value} is out of the range of {datatype}"))?
+            .write(endian, out);
+    }
+    Ok(())
+}
+
+fn encode_binary(
+    datatype: DataType,
+    value: &v2_proto::Value,
+    endian: Endian,
+) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    let Some(typed_value) = &value.typed_value else {
+        return Err(format!("An empty value is not a valid {datatype}"));
+    };
+    match (datatype, typed_value) {
+        (DataType::Boolean, TypedValue::Bool(value)) => out.push(u8::from(*value)),
+        (DataType::BooleanArray, TypedValue::BoolArray(array)) => {
+            out.extend(array.values.iter().map(|value| u8::from(*value)))
+        }
+        (DataType::Int8, TypedValue::Int32(value)) => {
+            write_all::<i8, _>(&[*value], endian, datatype, &mut out)?
+        }
+        (DataType::Int16, TypedValue::Int32(value)) => {
+            write_all::<i16, _>(&[*value], endian, datatype, &mut out)?
+        }
+        (DataType::Int32, TypedValue::Int32(value)) => value.write(endian, &mut out),
+        (DataType::Int64, TypedValue::Int64(value)) => value.write(endian, &mut out),
+        (DataType::Uint8, TypedValue::Uint32(value)) => {
+            write_all::<u8, _>(&[*value], endian, datatype, &mut out)?
+        }
+        (DataType::Uint16, TypedValue::Uint32(value)) => {
+            write_all::<u16, _>(&[*value], endian, datatype, &mut out)?
+        }
+        (DataType::Uint32, TypedValue::Uint32(value)) => value.write(endian, &mut out),
+        (DataType::Uint64, TypedValue::Uint64(value)) => value.write(endian, &mut out),
+        (DataType::Float, TypedValue::Float(value)) => value.write(endian, &mut out),
+        (DataType::Double, TypedValue::Double(value)) => value.write(endian, &mut out),
+        (DataType::Int8Array, TypedValue::Int32Array(array)) => {
+            write_all::<i8, _>(&array.values, endian, datatype, &mut out)?
+        }
+        (DataType::Int16Array, TypedValue::Int32Array(array)) => {
+            write_all::<i16, _>(&array.values, endian, datatype, &mut out)?
+        }
+        (DataType::Int32Array, TypedValue::Int32Array(array)) => {
+            write_all::<i32, _>(&array.values, endian, datatype, &mut out)?
+        }
+        (DataType::Int64Array, TypedValue::Int64Array(array)) => {
+            write_all::<i64, _>(&array.values, endian, datatype, &mut out)?
+        }
+        (DataType::Uint8Array, TypedValue::Uint32Array(array)) => {
+            write_all::<u8, _>(&array.values, endian, datatype, &mut out)?
+        }
+        (DataType::Uint16Array, TypedValue::Uint32Array(array)) => {
+            write_all::<u16, _>(&array.values, endian, datatype, &mut out)?
+        }
+        (DataType::Uint32Array, TypedValue::Uint32Array(array)) => {
+            write_all::<u32, _>(&array.values, endian, datatype, &mut out)?
+        }
+        (DataType::Uint64Array, TypedValue::Uint64Array(array)) => {
+            write_all::<u64, _>(&array.values, endian, datatype, &mut out)?
+        }
+        (DataType::FloatArray, TypedValue::FloatArray(array)) => array
+            .values
+            .iter()
+            .for_each(|value| value.write(endian, &mut out)),
+        (DataType::DoubleArray, TypedValue::DoubleArray(array)) => array
+            .values
+            .iter()
+            .for_each(|value| value.write(endian, &mut out)),
+        _ => {
+            return Err(format!(
+                "{typed_value:?} is not a valid {datatype} or has no binary encoding"
+            ));
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(typed_value: TypedValue) -> v2_proto::Value {
+        v2_proto::Value {
+            typed_value: Some(typed_value),
+        }
+    }
+
+    fn selector(text: &str) -> Selector {
+        Selector::try_from(text.to_owned()).unwrap()
+    }
+
+    fn decode_error(codec: Codec, datatype: DataType, payload: &[u8]) -> &'static str {
+        decode(codec, None, datatype, payload).unwrap_err().kind()
+    }
+
+    // Encode a value and decode the payload again with the same route settings
+    fn round_trip(
+        codec: Codec,
+        selector: Option<&Selector>,
+        datatype: DataType,
+        typed_value: TypedValue,
+    ) {
+        let payload = encode(codec, selector, datatype, value(typed_value.clone())).unwrap();
+        let decoded = decode(codec, selector, datatype, &payload).unwrap();
+        assert_eq!(decoded, value(typed_value), "{codec:?} {datatype}");
+    }
+
+    #[test]
+    fn selector_steps() {
+        assert_eq!(selector("$").steps, []);
+        assert_eq!(
+            selector("$.values[1]").steps,
+            [Step::Field("values".to_owned()), Step::Index(1)]
+        );
+        assert_eq!(
+            selector("$['outside temp'].celsius").steps,
+            [
+                Step::Field("outside temp".to_owned()),
+                Step::Field("celsius".to_owned())
+            ]
+        );
+        assert!(selector("$.a[\"b\"]").is_fields());
+        assert!(!selector("$.a[0]").is_fields());
+    }
+
+    #[test]
+    fn invalid_selectors() {
+        for text in [
+            "", "temp", "$temp", "$.", "$..temp", "$[temp]", "$[-1]", "$[0",
+        ] {
+            assert!(
+                Selector::try_from(text.to_owned()).is_err(),
+                "{text:?} was accepted"
+            );
+        }
+    }
+
+    #[test]
+    fn selector_wraps_outbound_value() {
+        let payload = encode(
+            Codec::Json,
+            Some(&selector("$.cabin['temp']")),
+            DataType::Float,
+            value(TypedValue::Float(21.5)),
+        )
+        .unwrap();
+        assert_eq!(payload, br#"{"cabin":{"temp":21.5}}"#);
+    }
+
+    #[test]
+    fn selector_picks_inbound_value() {
+        let payload = br#"{"values":[1,200],"unit":"km"}"#;
+        let decoded = decode(
+            Codec::Json,
+            Some(&selector("$.values[1]")),
+            DataType::Uint8,
+            payload,
+        );
+        assert_eq!(decoded.unwrap(), value(TypedValue::Uint32(200)));
+        let missing = decode(
+            Codec::Json,
+            Some(&selector("$.values[2]")),
+            DataType::Uint8,
+            payload,
+        );
+        assert_eq!(missing.unwrap_err().kind(), "not-found");
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let cases = [
+            (DataType::String, TypedValue::String("on, off".to_owned())),
+            (DataType::Boolean, TypedValue::Bool(true)),
+            (DataType::Int8, TypedValue::Int32(-128)),
+            (DataType::Uint64, TypedValue::Uint64(u64::MAX)),
+            (DataType::Float, TypedValue::Float(21.5)),
+            (DataType::Float, TypedValue::Float(21.456)),
+            (DataType::Double, TypedValue::Double(-0.125)),
+            (DataType::Double, TypedValue::Double(0.1 + 0.2)),
+            (
+                DataType::Int16Array,
+                TypedValue::Int32Array(v2_proto::Int32Array {
+                    values: vec![1, -2],
+                }),
+            ),
+            (
+                DataType::StringArray,
+                TypedValue::StringArray(v2_proto::StringArray {
+                    values: vec!["a".to_owned(), "b".to_owned()],
+                }),
+            ),
+            (
+                DataType::StringArray,
+                TypedValue::StringArray(v2_proto::StringArray {
+                    values: vec!["on, off".to_owned(), "it's".to_owned()],
+                }),
+            ),
+            (
+                DataType::BooleanArray,
+                TypedValue::BoolArray(v2_proto::BoolArray { values: vec![] }),
+            ),
+        ];
+        for (datatype, typed_value) in cases {
+            round_trip(Codec::Text, None, datatype, typed_value);
+        }
+    }
+
+    #[test]
+    fn text_is_lossless() {
+        let float = value(TypedValue::Float(21.456));
+        assert_eq!(
+            encode(Codec::Text, None, DataType::Float, float).unwrap(),
+            b"21.456"
+        );
+        let strings = value(TypedValue::StringArray(v2_proto::StringArray {
+            values: vec!["a, b".to_owned(), "c".to_owned()],
+        }));
+        assert_eq!(
+            encode(Codec::Text, None, DataType::StringArray, strings).unwrap(),
+            br#"["a, b","c"]"#
+        );
+    }
+
+    #[test]
+    fn text_lists() {
+        let expected = value(TypedValue::Uint32Array(v2_proto::Uint32Array {
+            values: vec![1, 2, 3],
+        }));
+        for payload in ["1, 2, 3", "[1,2,3]", " [ 1 , 2 , 3 ] "] {
+            let decoded = decode(Codec::Text, None, DataType::Uint8Array, payload.as_bytes());
+            assert_eq!(decoded.unwrap(), expected, "{payload:?}");
+        }
+        let decoded = decode(Codec::Text, None, DataType::StringArray, b"'a', \"b\", c");
+        assert_eq!(
+            decoded.unwrap(),
+            value(TypedValue::StringArray(v2_proto::StringArray {
+                values: vec!["a".to_owned(), "b".to_owned(), "c".to_owned()],
+            }))
+        );
+        assert_eq!(
+            decode_error(Codec::Text, DataType::Int32Array, b"1, x, 3"),
+            "invalid-value"
+        );
+    }
+
+    #[test]
+    fn text_rejects_invalid_values() {
+        assert_eq!(
+            decode_error(Codec::Text, DataType::Int8, b"128"),
+            "invalid-value"
+        );
+        assert_eq!(
+            decode_error(Codec::Text, DataType::Uint32, b"-1"),
+            "invalid-value"
+        );
+        assert_eq!(
+            decode_error(Codec::Text, DataType::Boolean, b"yes"),
+            "invalid-value"
+        );
+        assert_eq!(
+            decode_error(Codec::Text, DataType::String, &[0xFF, 0xFE]),
+            "not-utf8"
+        );
+    }
+
+    #[test]
+    fn document_round_trips() {
+        let cases = [
+            (
+                DataType::String,
+                TypedValue::String("warm white".to_owned()),
+            ),
+            (DataType::Boolean, TypedValue::Bool(false)),
+            (DataType::Int64, TypedValue::Int64(i64::MIN)),
+            (DataType::Uint64, TypedValue::Uint64(u64::MAX)),
+            (DataType::Float, TypedValue::Float(21.5)),
+            (DataType::Double, TypedValue::Double(1e300)),
+            (
+                DataType::DoubleArray,
+                TypedValue::DoubleArray(v2_proto::DoubleArray {
+                    values: vec![0.5, -1.0],
+                }),
+            ),
+            (
+                DataType::Int8Array,
+                TypedValue::Int32Array(v2_proto::Int32Array {
+                    values: vec![-128, 127],
+                }),
+            ),
+        ];
+        let nested = selector("$.data.value");
+        for codec in [Codec::Json, Codec::Cbor, Codec::Msgpack] {
+            for (datatype, typed_value) in cases.clone() {
+                round_trip(codec, None, datatype, typed_value.clone());
+                round_trip(codec, Some(&nested), datatype, typed_value);
+            }
+        }
+    }
+
+    #[test]
+    fn documents_reject_invalid_payloads() {
+        for codec in [Codec::Json, Codec::Cbor, Codec::Msgpack] {
+            assert_eq!(
+                decode_error(codec, DataType::Float, &[0xC1]),
+                "malformed",
+                "{codec:?}"
+            );
+        }
+        assert_eq!(
+            decode_error(Codec::Json, DataType::Float, b"{"),
+            "malformed"
+        );
+        assert_eq!(
+            decode_error(Codec::Json, DataType::Float, b"\"21.5\""),
+            "invalid-value"
+        );
+        assert_eq!(
+            decode_error(Codec::Json, DataType::Uint8, b"256"),
+            "invalid-value"
+        );
+        assert_eq!(
+            decode_error(Codec::Json, DataType::Int16Array, b"[1, 40000]"),
+            "invalid-value"
+        );
+    }
+
+    #[test]
+    fn binary_byte_order() {
+        let int16 = value(TypedValue::Int32(0x0102));
+        assert_eq!(
+            encode(Codec::BinaryLe, None, DataType::Int16, int16.clone()).unwrap(),
+            [0x02, 0x01]
+        );
+        assert_eq!(
+            encode(Codec::BinaryBe, None, DataType::Int16, int16.clone()).unwrap(),
+            [0x01, 0x02]
+        );
+        assert_eq!(
+            decode(Codec::BinaryLe, None, DataType::Int16, &[0x02, 0x01]).unwrap(),
+            int16
+        );
+        assert_eq!(
+            decode(Codec::BinaryBe, None, DataType::Int16, &[0x01, 0x02]).unwrap(),
+            int16
+        );
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        let cases = [
+            (DataType::Boolean, TypedValue::Bool(true)),
+            (DataType::Uint8, TypedValue::Uint32(255)),
+            (DataType::Int32, TypedValue::Int32(i32::MIN)),
+            (DataType::Uint64, TypedValue::Uint64(u64::MAX)),
+            (DataType::Float, TypedValue::Float(-21.5)),
+            (DataType::Double, TypedValue::Double(f64::MAX)),
+            (
+                DataType::BooleanArray,
+                TypedValue::BoolArray(v2_proto::BoolArray {
+                    values: vec![true, false],
+                }),
+            ),
+            (
+                DataType::Uint16Array,
+                TypedValue::Uint32Array(v2_proto::Uint32Array {
+                    values: vec![0, 65535],
+                }),
+            ),
+            (
+                DataType::FloatArray,
+                TypedValue::FloatArray(v2_proto::FloatArray {
+                    values: vec![0.5, -1.5],
+                }),
+            ),
+        ];
+        for codec in [Codec::BinaryLe, Codec::BinaryBe] {
+            for (datatype, typed_value) in cases.clone() {
+                round_trip(codec, None, datatype, typed_value);
+            }
+        }
+    }
+
+    #[test]
+    fn binary_narrows_to_the_datatype() {
+        let array = value(TypedValue::Int32Array(v2_proto::Int32Array {
+            values: vec![1, -2],
+        }));
+        assert_eq!(
+            encode(Codec::BinaryLe, None, DataType::Int8Array, array.clone()).unwrap(),
+            [0x01, 0xFE]
+        );
+        assert_eq!(
+            encode(Codec::BinaryBe, None, DataType::Int16Array, array).unwrap(),
+            [0x00, 0x01, 0xFF, 0xFE]
+        );
+        assert!(
+            encode(
+                Codec::BinaryLe,
+                None,
+                DataType::Int8,
+                value(TypedValue::Int32(128))
+            )
+            .is_err()
+        );
+        assert!(
+            encode(
+                Codec::BinaryLe,
+                None,
+                DataType::Uint16,
+                value(TypedValue::Uint32(65536))
+            )
+            .is_err()
+        );
+        assert!(
+            encode(
+                Codec::BinaryLe,
+                None,
+                DataType::Int32,
+                value(TypedValue::Int64(1))
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn binary_rejects_invalid_payloads() {
+        assert_eq!(
+            decode_error(Codec::BinaryLe, DataType::Int32, &[1, 2, 3]),
+            "invalid-length"
+        );
+        assert_eq!(
+            decode_error(Codec::BinaryLe, DataType::Int16Array, &[1, 2, 3]),
+            "invalid-length"
+        );
+        assert_eq!(
+            decode_error(Codec::BinaryLe, DataType::Boolean, &[2]),
+            "invalid-value"
+        );
+        assert_eq!(
+            decode_error(Codec::BinaryLe, DataType::String, b"on"),
+            "unsupported"
+        );
+        assert_eq!(
+            decode(Codec::BinaryLe, None, DataType::Uint8Array, &[]).unwrap(),
+            value(TypedValue::Uint32Array(v2_proto::Uint32Array {
+                values: vec![]
+            }))
+        );
+    }
+
+    #[test]
+    fn floats_reject_nan_infinity_and_overflow() {
+        for payload in ["nan", "NaN", "inf", "-inf", "1e39"] {
+            assert_eq!(
+                decode_error(Codec::Text, DataType::Float, payload.as_bytes()),
+                "invalid-value",
+                "{payload:?}"
+            );
+        }
+        assert_eq!(
+            decode(Codec::Text, None, DataType::Double, b"1e39").unwrap(),
+            value(TypedValue::Double(1e39))
+        );
+        assert_eq!(
+            decode_error(Codec::Json, DataType::Float, b"1e39"),
+            "invalid-value"
+        );
+        assert_eq!(
+            decode_error(Codec::Text, DataType::DoubleArray, b"1, nan"),
+            "invalid-value"
+        );
+        let nan = f32::NAN.to_le_bytes();
+        assert_eq!(
+            decode_error(Codec::BinaryLe, DataType::Float, &nan),
+            "invalid-value"
+        );
+        let infinity = [1.0, f64::INFINITY].map(f64::to_be_bytes).concat();
+        assert_eq!(
+            decode_error(Codec::BinaryBe, DataType::DoubleArray, &infinity),
+            "invalid-value"
+        );
+    }
+
+    #[test]
+    fn rgb_led_driver_payload() {
+        let color = |text: &str| value(TypedValue::String(text.to_owned()));
+        assert_eq!(
+            encode(Codec::Rgb, None, DataType::String, color("blue 50%")).unwrap(),
+            b"'000080'"
+        );
+        assert_eq!(
+            decode(Codec::Rgb, None, DataType::String, b"'00ff80'").unwrap(),
+            color("00FF80")
+        );
+        assert_eq!(
+            decode(Codec::Rgb, None, DataType::String, b"000080").unwrap(),
+            color("000080")
+        );
+        assert!(encode(Codec::Rgb, None, DataType::String, color("chartreuse")).is_err());
+        assert_eq!(
+            decode_error(Codec::Rgb, DataType::String, &[0x00, 0x00, 0x80]),
+            "not-utf8"
+        );
+        assert_eq!(
+            decode_error(Codec::Rgb, DataType::String, b"blue"),
+            "invalid-value"
+        );
+    }
+}
@@ -0,0 +1,295 @@
+// Copyright (c) 2025 Eclipse Foundation and others.
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use std::fmt;
+
+// Named colors, tuned for the RGB LED rather than a screen
+const NAMED: [(&str, Rgb); 15] = [
+    ("off", Rgb::new(0x00, 0x00, 0x00)),
+    ("black", Rgb::new(0x00, 0x00, 0x00)),
+    ("white", Rgb::new(0xFF, 0xFF, 0xFF)),
+    // About 3000 K and 6500 K
+    ("warm white", Rgb::new(0xFF, 0xB4, 0x6B)),
+    ("cool white", Rgb::new(0xFF, 0xF9, 0xFD)),
+    ("red", Rgb::new(0xFF, 0x00, 0x00)),
+    ("green", Rgb::new(0x00, 0xFF, 0x00)),
+    ("blue", Rgb::new(0x00, 0x00, 0xFF)),
+    ("yellow", Rgb::new(0xFF, 0xFF, 0x00)),
+    ("cyan", Rgb::new(0x00, 0xFF, 0xFF)),
+    ("magenta", Rgb::new(0xFF, 0x00, 0xFF)),
+    ("orange", Rgb::new(0xFF, 0x80, 0x00)),
+    ("purple", Rgb::new(0x80, 0x00, 0xFF)),
+    ("pink", Rgb::new(0xFF, 0x40, 0x80)),
+    ("amber", Rgb::new(0xFF, 0xBF, 0x00)),
+];
+
+// Color of the RGB LED
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Rgb {
+    red: u8,
+    green: u8,
+    blue: u8,
+}
+
+impl Rgb {
+    const fn new(red: u8, green: u8, blue: u8) -> Self {
+        Rgb { red, green, blue }
+    }
+
+    // Color of an LED driver payload, the quotes are optional
+    pub(crate) fn from_payload(payload: &str) -> Option<Self> {
+        let payload = payload.trim();
+        let digits = payload
+            .strip_prefix('\'')
+            .and_then(|digits| digits.strip_suffix('\''))
+            .unwrap_or(payload);
+        code(digits)
+    }
+
+    // Payload of the LED driver of the MCU firmware: the hex code in single quotes,
+    // e.g. 'FF0000', as the provider published VSS strings before
+    pub(crate) fn payload(&self) -> String {
+        format!("'{self}'")
+    }
+
+    // The same hue at the given brightness in percent
+    fn dimmed(self, brightness: f64) -> Self {
+        let scale = |channel: u8| (f64::from(channel) * brightness / 100.0).round() as u8;
+        Rgb::new(scale(self.red), scale(self.green), scale(self.blue))
+    }
+}
+
+// VSS color code "RRGGBB"
+impl fmt::Display for Rgb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02X}{:02X}{:02X}", self.red, self.green, self.blue)
+    }
+}
+
+// Parse a color as set in VSS or by a user, with an optional brightness in percent
+// after it, e.g. "FF0000", "#ff0000", "warm white", "rgb(255, 0, 0)",
+// "hsv(240, 100%, 50%)" or "blue 30%"
+pub(crate) fn parse(text: &str) -> Result<Rgb, String> {
+    let text = text.trim();
+    let (color, brightness) = match text.rsplit_once(char::is_whitespace) {
+        Some((color, last)) if last.ends_with('%') => (color.trim_end(), Some(last)),
+        _ => (text, None),
+    };
+    let rgb = parse_color(color)?;
+    match brightness {
+        Some(brightness) => Ok(rgb.dimmed(percent(brightness, "Brightness")?)),
+        None => Ok(rgb),
+    }
+}
+
+fn parse_color(text: &str) -> Result<Rgb, String> {
+    if let Some(args) = function(text, "rgb") {
+        let [red, green, blue] = arguments(args, text)?;
+        let channel = |arg: &str, name: &str| {
+            arg.parse::<u8>()
+                .map_err(|_| format!("{name} {arg:?} of {text} must be an integer from 0 to 255"))
+        };
+        return Ok(Rgb::new(
+            channel(red, "Red")?,
+            channel(green, "Green")?,
+            channel(blue, "Blue")?,
+        ));
+    }
+    if let Some(args) = function(text, "hsv") {
+        let [hue, saturation, value] = arguments(args, text)?;
+        let hue = hue
+            .parse::<f64>()
+            .ok()
+            .filter(|hue| (0.0..=360.0).contains(hue))
+            .ok_or_else(|| format!("Hue {hue:?} of {text} must be a number from 0 to 360"))?;
+        return Ok(hsv(
+            hue,
+            percent(saturation, "Saturation")? / 100.0,
+            percent(value, "Value")? / 100.0,
+        ));
+    }
+    if let Some(rgb) = hex(text) {
+        return Ok(rgb);
+    }
+    let name = text
+        .split([' ', '-', '_'])
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    NAMED
+        .iter()
+        .find(|(named, _)| *named == name)
+        .map(|(_, rgb)| *rgb)
+        .ok_or_else(|| {
+            format!(
+                "{text:?} is not a color, expected a hex code like \"FF0000\", a name like \"blue\", rgb(r, g, b) or hsv(h, s%, v%)"
+            )
+        })
+}
+
+// Hex color code "RRGGBB", optionally prefixed with "#" or "0x"
+fn hex(text: &str) -> Option<Rgb> {
+    let digits = text
+        .strip_prefix('#')
+        .or_else(|| text.strip_prefix("0x"))
+        .or_else(|| text.strip_prefix("0X"))
+        .unwrap_or(text);
+    code(digits)
+}
+
+// Exactly the six hex digits "RRGGBB"
+fn code(digits: &str) -> Option<Rgb> {
+    if digits.len() != 6 || !digits.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |at: usize| u8::from_str_radix(&digits[at..at + 2], 16).ok();
+    Some(Rgb::new(channel(0)?, channel(2)?, channel(4)?))
+}
+
+// Arguments of "name(...)", case-insensitive
+fn function<'a>(text: &'a str, name: &str) -> Option<&'a str> {
+    let (function, rest) = text.split_at_checked(name.len())?;
+    if !function.eq_ignore_ascii_case(name) {
+        return None;
+    }
+    rest.trim_start().strip_prefix('(')?.strip_suffix(')')
+}
+
+fn arguments<'a>(args: &'a str, text: &str) -> Result<[&'a str; 3], String> {
+    let args: Vec<&str> = args.split(',').map(str::trim).collect();
+    args.try_into()
+        .map_err(|_| format!("{text} must have 3 comma separated components"))
+}
+
+// Percentage from 0 to 100, the "%" is optional
+fn percent(text: &str, name: &str) -> Result<f64, String> {
+    text.strip_suffix('%')
+        .unwrap_or(text)
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|percent| (0.0..=100.0).contains(percent))
+        .ok_or_else(|| format!("{name} {text:?} must be a percentage from 0 to 100"))
+}
+
+// Hue in degrees, saturation and value from 0 to 1
+fn hsv(hue: f64, saturation: f64, value: f64) -> Rgb {
+    let chroma = value * saturation;
+    let sector = (hue % 360.0) / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (red, green, blue) = match sector as u8 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = value - chroma;
+    let channel = |channel: f64| ((channel + m) * 255.0).round() as u8;
+    Rgb::new(channel(red), channel(green), channel(blue))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_codes() {
+        assert_eq!(parse("FF0000"), Ok(Rgb::new(0xFF, 0x00, 0x00)));
+        assert_eq!(parse("#00ff80"), Ok(Rgb::new(0x00, 0xFF, 0x80)));
+        assert_eq!(parse(" 0x0000FF "), Ok(Rgb::new(0x00, 0x00, 0xFF)));
+        assert_eq!(parse("0XFFFFFF"), Ok(Rgb::new(0xFF, 0xFF, 0xFF)));
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(parse("blue"), Ok(Rgb::new(0x00, 0x00, 0xFF)));
+        assert_eq!(parse("Warm White"), Ok(Rgb::new(0xFF, 0xB4, 0x6B)));
+        assert_eq!(parse("warm-white"), Ok(Rgb::new(0xFF, 0xB4, 0x6B)));
+        assert_eq!(parse("COOL_WHITE"), Ok(Rgb::new(0xFF, 0xF9, 0xFD)));
+        assert_eq!(parse("off"), Ok(Rgb::new(0x00, 0x00, 0x00)));
+    }
+
+    #[test]
+    fn rgb_tuples() {
+        assert_eq!(parse("rgb(255, 128, 0)"), Ok(Rgb::new(255, 128, 0)));
+        assert_eq!(parse("RGB(1,2,3)"), Ok(Rgb::new(1, 2, 3)));
+        assert_eq!(parse("rgb ( 0 , 0 , 0 )"), Ok(Rgb::new(0, 0, 0)));
+    }
+
+    #[test]
+    fn hsv_tuples() {
+        assert_eq!(parse("hsv(0, 100%, 100%)"), Ok(Rgb::new(255, 0, 0)));
+        assert_eq!(parse("hsv(120, 100, 100)"), Ok(Rgb::new(0, 255, 0)));
+        assert_eq!(parse("hsv(240, 100%, 50%)"), Ok(Rgb::new(0, 0, 128)));
+        assert_eq!(parse("hsv(360, 100%, 100%)"), Ok(Rgb::new(255, 0, 0)));
+        assert_eq!(parse("hsv(30, 100%, 100%)"), Ok(Rgb::new(255, 128, 0)));
+        assert_eq!(parse("HSV(0, 0%, 100%)"), Ok(Rgb::new(255, 255, 255)));
+    }
+
+    #[test]
+    fn brightness_suffix() {
+        assert_eq!(parse("blue 50%"), Ok(Rgb::new(0, 0, 128)));
+        assert_eq!(parse("warm white 30%"), Ok(Rgb::new(77, 54, 32)));
+        assert_eq!(parse("#FF0000 100%"), Ok(Rgb::new(255, 0, 0)));
+        assert_eq!(parse("rgb(255, 128, 0)  0%"), Ok(Rgb::new(0, 0, 0)));
+        assert_eq!(parse("hsv(0, 100%, 100%) 50%"), Ok(Rgb::new(128, 0, 0)));
+    }
+
+    #[test]
+    fn invalid_colors() {
+        for text in [
+            "",
+            "ff00",
+            "FF00000",
+            "GG0000",
+            "chartreuse",
+            "rgb(256, 0, 0)",
+            "rgb(-1, 0, 0)",
+            "rgb(1, 2)",
+            "rgb(1, 2, 3",
+            "hsv(361, 100%, 100%)",
+            "hsv(0, 150%, 100%)",
+            "hsv(0, 100%)",
+            "blue 120%",
+            "blue -10%",
+            "blue x%",
+            "50%",
+        ] {
+            assert!(parse(text).is_err(), "{text:?} was accepted");
+        }
+    }
+
+    #[test]
+    fn led_driver_payload() {
+        let rgb = Rgb::new(0x00, 0x00, 0x80);
+        assert_eq!(rgb.payload(), "'000080'");
+        assert_eq!(rgb.to_string(), "000080");
+        assert_eq!(Rgb::from_payload("'000080'"), Some(rgb));
+        assert_eq!(
+            Rgb::from_payload("00008a"),
+            Some(Rgb::new(0x00, 0x00, 0x8A))
+        );
+        assert_eq!(Rgb::from_payload("'#000080'"), None);
+        assert_eq!(Rgb::from_payload("'00008'"), None);
+        assert_eq!(Rgb::from_payload("blue"), None);
+    }
+}
@@ -0,0 +1,74 @@
+// Copyright (c) 2025 Eclipse Foundation and others.
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::codec::DecodeError;
+use crate::mapping::InboundRoute;
+use carmate_service::health;
+use paho_mqtt::{self as mqtt};
+use serde::Serialize;
+use std::fmt::Write;
+
+// MQTT message rejected by the bridge, published on the dead-letter topic
+#[derive(Serialize, Debug)]
+struct DeadLetter<'a> {
+    topic: &'a str,
+    path: &'a str,
+    // The payload as text if it is UTF-8, hex encoded otherwise
+    payload: String,
+    payload_encoding: &'static str,
+    // Machine readable reason, e.g. "invalid-value"
+    error: &'static str,
+    reason: String,
+    // Milliseconds since the Unix epoch
+    timestamp: i64,
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut out, byte| {
+        let _ = write!(out, "{byte:02x}");
+        out
+    })
+}
+
+// JSON message about a payload received on an inbound route that was not published to Kuksa
+pub(crate) fn message(
+    topic: &str,
+    route: &InboundRoute,
+    payload: &[u8],
+    err: &DecodeError,
+) -> Result<mqtt::Message, serde_json::Error> {
+    let (payload, payload_encoding) = match str::from_utf8(payload) {
+        Ok(text) => (text.to_owned(), "utf-8"),
+        Err(_) => (hex(payload), "hex"),
+    };
+    let dead_letter = DeadLetter {
+        topic: &route.topic,
+        path: &route.path,
+        payload,
+        payload_encoding,
+        error: err.kind(),
+        reason: err.to_string(),
+        timestamp: health::now_millis(),
+    };
+    Ok(mqtt::Message::new(
+        topic,
+        serde_json::to_vec(&dead_letter)?,
+        mqtt::QOS_1,
+    ))
+}
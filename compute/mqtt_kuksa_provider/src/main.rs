@@ -17,13 +17,85 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
+mod actuation;
+mod codec;
+mod color;
+mod dead_letter;
+mod health;
+mod mapping;
+mod metrics;
+
+use actuation::{Failure, Provider};
+use carmate_service::telemetry::{self, LogFormat};
+use carmate_service::zenoh_options::{ZenohArgs, ZenohOptions};
+use carmate_service::{http, shutdown};
+use clap::Parser;
+use health::Health;
 use kuksa_rust_sdk::kuksa::common::ClientTraitV2;
 use kuksa_rust_sdk::kuksa::val::v2::KuksaClientV2;
 use kuksa_rust_sdk::v2_proto;
+use mapping::{InboundRoute, Mapping, OutboundRoute};
+use metrics::Metrics;
 use paho_mqtt::{self as mqtt};
 use std::fmt;
-use std::time::Duration;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
+use tonic::transport::Uri;
+use tracing::{Instrument, debug, error, info, info_span, warn};
+use up_rust::UUID;
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Mapping file (TOML) with the routes between MQTT topics and VSS signals
+    #[clap(long, env = "MKP_MAPPING", default_value = "mapping.toml")]
+    mapping: PathBuf,
+    /// URL of the Kuksa Databroker
+    #[clap(
+        long,
+        env = "MKP_KUKSA_URL",
+        default_value = "http://localhost:55555",
+        value_parser = kuksa_uri
+    )]
+    kuksa_url: Uri,
+    /// URL of the MQTT server, e.g. mqtt://host:1883
+    #[clap(long, env = "MKP_MQTT_URL", default_value = "mqtt://localhost:1883")]
+    mqtt_url: String,
+    /// Log output format
+    #[clap(long, value_enum, env = "LOG_FORMAT", default_value_t)]
+    log_format: LogFormat,
+    /// OpenTelemetry collector to export spans to over OTLP/gRPC, e.g. http://localhost:4317
+    #[clap(long, env = "OTEL_EXPORTER_OTLP_ENDPOINT")]
+    otlp_endpoint: Option<String>,
+    #[clap(flatten)]
+    zenoh: ZenohArgs,
+}
+
+// Kuksa Databroker URL, rejected at startup instead of failing on every request
+fn kuksa_uri(url: &str) -> Result<Uri, String> {
+    let uri: Uri = url
+        .parse()
+        .map_err(|err| format!("Invalid Kuksa URL {url:?}: {err}"))?;
+    if !matches!(uri.scheme_str(), Some("http") | Some("https")) {
+        return Err(format!(
+            "Kuksa URL {url:?} must start with http:// or https://"
+        ));
+    }
+    Ok(uri)
+}
+
+// Log filter unless set with RUST_LOG
+const DEFAULT_LOG_LEVEL: &str = "info";
+// Interval of the MQTT connection check and of the confirmation timeouts
+const TICK: Duration = Duration::from_millis(100);
+// Delays between attempts to reach the MQTT server, doubling up to the maximum
+const RECONNECT_MIN: Duration = Duration::from_secs(1);
+const RECONNECT_MAX: Duration = Duration::from_secs(30);
+
+// An MQTT message: topic, payload and the time it was received
+type Received = (String, Vec<u8>, Instant);
 
 struct DisplayDatapoint(v2_proto::Value);
 
@@ -68,9 +140,354 @@ impl fmt::Display for DisplayDatapoint {
     }
 }
 
-fn vec_u8_to_f32_from_string(bytes: Vec<u8>) -> f32 {
-    let s = str::from_utf8(&bytes).unwrap_or(""); // Convert, default empty string on error
-    s.trim().parse().unwrap_or(0.0) // Parse float, return 0.0 on error
+// Correlation id of one message through the bridge, logged with every event about it
+fn correlation_id() -> String {
+    UUID::build().to_hyphenated_string()
+}
+
+// Publish a value received from MQTT at the given time to Kuksa, or reject it
+// on the dead-letter topic if the payload is not a valid value of the signal
+async fn publish_inbound(
+    v2_client: &mut KuksaClientV2,
+    mqtt_client: &mqtt::AsyncClient,
+    health: &Health,
+    metrics: &Metrics,
+    route: &InboundRoute,
+    dead_letter_topic: &str,
+    msg: (Vec<u8>, Instant),
+) {
+    let span = info_span!(
+        "mqtt_message",
+        source = "mqtt",
+        topic = %route.topic,
+        path = %route.path,
+        correlation_id = %correlation_id(),
+    );
+    forward_inbound(
+        v2_client,
+        mqtt_client,
+        health,
+        metrics,
+        route,
+        dead_letter_topic,
+        msg,
+    )
+    .instrument(span)
+    .await
+}
+
+async fn forward_inbound(
+    v2_client: &mut KuksaClientV2,
+    mqtt_client: &mqtt::AsyncClient,
+    health: &Health,
+    metrics: &Metrics,
+    route: &InboundRoute,
+    dead_letter_topic: &str,
+    (payload, received): (Vec<u8>, Instant),
+) {
+    info!(payload = %String::from_utf8_lossy(&payload), "Got message");
+    let decoded = codec::decode(
+        route.codec,
+        route.selector.as_ref(),
+        route.datatype,
+        &payload,
+    );
+    let value = match decoded {
+        Ok(value) => value,
+        Err(err) => {
+            warn!(error = err.kind(), %err, "Rejected invalid message");
+            metrics.invalid_message(&route.topic, err.kind());
+            match dead_letter::message(dead_letter_topic, route, &payload, &err) {
+                Ok(msg) => publish_mqtt(mqtt_client, health, metrics, msg).await,
+                Err(err) => warn!(?err, "Encoding dead letter failed"),
+            }
+            return;
+        }
+    };
+    let typed_value = value.typed_value.clone();
+    let result = v2_client.publish_value(route.path.clone(), value).await;
+    match result {
+        Ok(()) => {
+            health.kuksa.succeeded();
+            metrics.kuksa_published(&route.path);
+            metrics.latency(&route.path, received.elapsed().as_secs_f64());
+            info!(value = ?typed_value, "Published value");
+        }
+        Err(err) => {
+            warn!(?err, "Publishing value failed");
+            health.kuksa.failed();
+            metrics.grpc_error(&err);
+        }
+    }
+}
+
+// Publish a message to the MQTT broker, returns whether the broker accepted it
+async fn publish_mqtt(
+    mqtt_client: &mqtt::AsyncClient,
+    health: &Health,
+    metrics: &Metrics,
+    msg: mqtt::Message,
+) -> bool {
+    let topic = msg.topic().to_owned();
+    match mqtt_client.publish(msg).await {
+        Ok(()) => {
+            health.mqtt.succeeded();
+            metrics.mqtt_published(&topic);
+            true
+        }
+        Err(err) => {
+            warn!(%topic, ?err, "Publishing to MQTT failed");
+            health.mqtt.failed();
+            false
+        }
+    }
+}
+
+// Connect to the MQTT server, retrying until it is reachable. Afterwards the
+// client reconnects by itself.
+async fn connect(mqtt_client: &mqtt::AsyncClient, options: mqtt::ConnectOptions, health: &Health) {
+    let mut delay = RECONNECT_MIN;
+    while let Err(err) = mqtt_client.connect(options.clone()).await {
+        warn!(?err, ?delay, "Connecting to the MQTT server failed");
+        health.mqtt.failed();
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(RECONNECT_MAX);
+    }
+}
+
+// Subscribe to the given topics, on every (re)connect as the broker does not keep
+// the subscriptions of a clean session
+async fn subscribe(
+    mqtt_client: mqtt::AsyncClient,
+    topics: Vec<String>,
+    qos: Vec<i32>,
+    health: Arc<Health>,
+) {
+    if topics.is_empty() {
+        return;
+    }
+    match mqtt_client.subscribe_many(&topics, &qos).await {
+        Ok(_) => info!(?topics, "Subscribed"),
+        Err(err) => {
+            warn!(?topics, ?err, "Subscribing failed");
+            health.mqtt.failed();
+        }
+    }
+}
+
+// MQTT message of an outbound route
+fn outbound_message(route: &OutboundRoute, payload: Vec<u8>) -> mqtt::Message {
+    if route.retain {
+        mqtt::Message::new_retained(&route.topic, payload, route.qos)
+    } else {
+        mqtt::Message::new(&route.topic, payload, route.qos)
+    }
+}
+
+// Forward MQTT messages to Kuksa and actuation requests from Kuksa to MQTT
+async fn run(
+    v2_client: &mut KuksaClientV2,
+    mqtt_client: &mqtt::AsyncClient,
+    rx: &mut mpsc::Receiver<Received>,
+    provider: &mut Provider,
+    mapping: &Mapping,
+    health: &Health,
+    metrics: &Metrics,
+) {
+    let mut ticker = tokio::time::interval(TICK);
+    loop {
+        tokio::select! {
+            msg = rx.recv() => match msg {
+                Some(msg) => {
+                    forward_received(v2_client, mqtt_client, provider, mapping, health, metrics, msg)
+                        .await
+                }
+                None => {
+                    // Channel closed, no more messages possible
+                    warn!("Channel closed");
+                    return;
+                }
+            },
+            Some(request) = provider.next() => {
+                match mapping.outbound.iter().find(|route| route.path == request.path) {
+                    Some(route) => {
+                        let span = info_span!(
+                            "actuation",
+                            source = "kuksa",
+                            path = %route.path,
+                            topic = %route.topic,
+                            correlation_id = %request.correlation_id,
+                        );
+                        actuate(mqtt_client, provider, route, request, health, metrics)
+                            .instrument(span)
+                            .await
+                    }
+                    None => {
+                        let failure = Failure::invalid("No outbound route for the actuator");
+                        provider.respond(&request, Err(failure)).await
+                    }
+                }
+            }
+            _ = ticker.tick() => {
+                if !mqtt_client.is_connected() {
+                    health.mqtt.set(health::ConnectionState::Disconnected);
+                }
+                provider.expire().await;
+            }
+        }
+    }
+}
+
+// Publish a received MQTT message to Kuksa on the route of its topic, or take it
+// as the MCU confirmation of an actuation
+async fn forward_received(
+    v2_client: &mut KuksaClientV2,
+    mqtt_client: &mqtt::AsyncClient,
+    provider: &mut Provider,
+    mapping: &Mapping,
+    health: &Health,
+    metrics: &Metrics,
+    (topic, payload, received): Received,
+) {
+    if let Some(route) = mapping.inbound.iter().find(|route| route.topic == topic) {
+        publish_inbound(
+            v2_client,
+            mqtt_client,
+            health,
+            metrics,
+            route,
+            &mapping.dead_letter_topic,
+            (payload, received),
+        )
+        .await
+    } else if let Some(route) = mapping
+        .outbound
+        .iter()
+        .find(|route| route.ack_topic.as_ref() == Some(&topic))
+    {
+        let request = provider.take_pending(&route.path);
+        let span = info_span!(
+            "confirmation",
+            source = "mqtt",
+            topic = %topic,
+            path = %route.path,
+            correlation_id = %request
+                .as_ref()
+                .map(|request| request.correlation_id.clone())
+                .unwrap_or_else(correlation_id),
+        );
+        confirm(
+            v2_client, provider, route, request, payload, health, metrics,
+        )
+        .instrument(span)
+        .await
+    } else {
+        debug!(%topic, "No route for topic");
+    }
+}
+
+// Forward an actuation request to the MCU, it is acknowledged once the MCU confirms it
+// on the confirmation topic of the route, or once the broker accepted it if there is none
+async fn actuate(
+    mqtt_client: &mqtt::AsyncClient,
+    provider: &mut Provider,
+    route: &OutboundRoute,
+    request: actuation::Request,
+    health: &Health,
+    metrics: &Metrics,
+) {
+    info!(value = ?request.value, "Got actuation request");
+    let Some(value) = request.value.clone() else {
+        let failure = Failure::invalid("Actuation request without value");
+        return provider.respond(&request, Err(failure)).await;
+    };
+    if let Some(typed_value) = &value.typed_value
+        && !codec::matches(route.datatype, typed_value)
+    {
+        let failure = Failure::invalid(format!(
+            "{typed_value:?} does not match the datatype {} of the route",
+            route.datatype
+        ));
+        return provider.respond(&request, Err(failure)).await;
+    }
+    let encoded = codec::encode(route.codec, route.selector.as_ref(), route.datatype, value);
+    let payload = match encoded {
+        Ok(payload) => payload,
+        Err(err) => return provider.respond(&request, Err(Failure::invalid(err))).await,
+    };
+    if !publish_mqtt(
+        mqtt_client,
+        health,
+        metrics,
+        outbound_message(route, payload),
+    )
+    .await
+    {
+        let failure = Failure::unavailable("Publishing to the MQTT broker failed");
+        return provider.respond(&request, Err(failure)).await;
+    }
+    match route.ack_topic {
+        Some(_) => {
+            let timeout = Duration::from_millis(route.ack_timeout_ms);
+            provider.wait_for_confirmation(request, timeout).await;
+        }
+        None => {
+            info!("Actuation forwarded");
+            provider.respond(&request, Ok(())).await;
+        }
+    }
+}
+
+// Publish the value the MCU confirmed as current value of the actuator and acknowledge
+// the actuation waiting for it
+async fn confirm(
+    v2_client: &mut KuksaClientV2,
+    provider: &Provider,
+    route: &OutboundRoute,
+    request: Option<actuation::Request>,
+    payload: Vec<u8>,
+    health: &Health,
+    metrics: &Metrics,
+) {
+    info!(payload = %String::from_utf8_lossy(&payload), "Got confirmation");
+    let decoded = codec::decode(
+        route.codec,
+        route.selector.as_ref(),
+        route.datatype,
+        &payload,
+    );
+    let value = match decoded {
+        Ok(value) => value,
+        Err(err) => {
+            warn!(error = err.kind(), %err, "Rejected invalid confirmation");
+            if let Some(topic) = &route.ack_topic {
+                metrics.invalid_message(topic, err.kind());
+            }
+            if let Some(request) = request {
+                let failure = Failure::invalid(format!("Invalid MCU confirmation: {err}"));
+                provider.respond(&request, Err(failure)).await;
+            }
+            return;
+        }
+    };
+
+    let typed_value = value.typed_value.clone();
+    match v2_client.publish_value(route.path.clone(), value).await {
+        Ok(()) => {
+            health.kuksa.succeeded();
+            metrics.kuksa_published(&route.path);
+            info!(value = ?typed_value, "Published confirmed value");
+        }
+        Err(err) => {
+            warn!(?err, "Publishing confirmed value failed");
+            health.kuksa.failed();
+            metrics.grpc_error(&err);
+        }
+    }
+    match request {
+        Some(request) => provider.respond(&request, Ok(())).await,
+        None => debug!("Confirmation without pending actuation"),
+    }
 }
 
 #[tokio::main]
@@ -79,98 +496,164 @@ async fn main() {
     // - Started after MQTT Broker and Kuksa Data broker
     // --> This is achieved with ankaios configuration
 
+    let args = Args::parse();
+
+    // Structured logging, optionally exported to an OpenTelemetry collector
+    let telemetry = telemetry::init(
+        health::SERVICE,
+        DEFAULT_LOG_LEVEL,
+        args.log_format,
+        args.otlp_endpoint.as_deref(),
+    )
+    .unwrap_or_else(|err| {
+        eprintln!("Initializing logging failed: {err}");
+        std::process::exit(1);
+    });
+
+    // Routes between MQTT topics and VSS signals
+    let mapping = mapping::load(&args.mapping).unwrap_or_else(|err| {
+        error!(mapping = %args.mapping.display(), %err, "Loading the mapping failed");
+        std::process::exit(1);
+    });
+    info!(
+        mapping = %args.mapping.display(),
+        inbound = mapping.inbound.len(),
+        outbound = mapping.outbound.len(),
+        "Loaded mapping"
+    );
+
+    // Zenoh session of the health messages
+    let zenoh_config = ZenohOptions::from(args.zenoh)
+        .build()
+        .unwrap_or_else(|err| {
+            error!(%err, "Invalid Zenoh configuration");
+            std::process::exit(1);
+        });
+
+    // Health reporting over uProtocol and HTTP, independent of the bridge
+    let health = Arc::new(Health::new());
+    let metrics = Arc::new(Metrics::default());
+    tokio::spawn(health::publish_periodically(health.clone(), zenoh_config));
+    {
+        let health = health.clone();
+        let metrics = metrics.clone();
+        tokio::spawn(async move {
+            let handler = move |path: &str| match path {
+                "/metrics" => metrics.response(&health),
+                _ => health::probe(path, &health),
+            };
+            if let Err(err) = http::serve(health::HTTP_PORT, handler).await {
+                warn!(port = health::HTTP_PORT, ?err, "HTTP server failed");
+            }
+        });
+    }
+
     // Initialize Kuksa Client
-    let host = "http://localhost:55555";
-    let mut v2_client: KuksaClientV2 = KuksaClientV2::from_host(host);
+    let host = args.kuksa_url.to_string();
+    let mut v2_client: KuksaClientV2 = KuksaClientV2::new(args.kuksa_url);
+
+    // Provide the actuators of the outbound routes
+    let actuators = mapping
+        .outbound
+        .iter()
+        .map(|route| route.path.clone())
+        .collect();
+    let mut provider = Provider::start(&host, actuators, health.clone(), metrics.clone());
 
     // Establish connection to MQTT Broker
-    let host = "mqtt://localhost:1883".to_string();
+    let host = args.mqtt_url;
 
-    println!("Connecting to the MQTT server at '{}'", host);
+    info!(%host, "Connecting to the MQTT server");
 
     // Create the client
-    let mqtt_client = mqtt::AsyncClient::new(host).unwrap();
+    let mqtt_client = mqtt::AsyncClient::new(host.as_str()).unwrap_or_else(|err| {
+        error!(%host, %err, "Creating the MQTT client failed");
+        std::process::exit(1);
+    });
 
-    // Connect with default options and wait for it to complete or fail
-    // The default is an MQTT v3.x connection.
-    let _ = mqtt_client.connect(None).await;
+    // Inbound topics and the confirmation topics of the outbound routes
+    let subscriptions: Vec<(String, i32)> = mapping
+        .inbound
+        .iter()
+        .map(|route| (route.topic.clone(), route.qos))
+        .chain(
+            mapping
+                .outbound
+                .iter()
+                .filter_map(|route| route.ack_topic.clone().map(|topic| (topic, route.qos))),
+        )
+        .collect();
+    let (topics, qos): (Vec<String>, Vec<i32>) = subscriptions.into_iter().unzip();
 
-    mqtt_client.subscribe("mcu/temperature", 1).wait().unwrap();
+    // Subscribe on every connect, the callback runs on a thread of the MQTT client
+    {
+        let runtime = tokio::runtime::Handle::current();
+        let topics = topics.clone();
+        let health = health.clone();
+        mqtt_client.set_connected_callback(move |client| {
+            health.mqtt.set(health::ConnectionState::Connected);
+            runtime.spawn(subscribe(
+                client.clone(),
+                topics.clone(),
+                qos.clone(),
+                health.clone(),
+            ));
+        });
+    }
 
-    let (tx, mut rx) = mpsc::channel::<Vec<u8>>(100);
+    let (tx, mut rx) = mpsc::channel::<Received>(100);
+    let callback_metrics = metrics.clone();
     mqtt_client.set_message_callback(move |_cli, msg_opt| {
         if let Some(msg) = msg_opt {
-            if msg.topic() == "mcu/temperature" {
-                let payload = msg.payload().to_vec();
-                println!("Got message payload: {:?}", payload);
-                let _ = tx.try_send(payload);
-            }
+            callback_metrics.mqtt_received(msg.topic());
+            let payload = msg.payload().to_vec();
+            debug!(topic = msg.topic(), ?payload, "Got message payload");
+            let _ = tx.try_send((msg.topic().to_owned(), payload, Instant::now()));
         }
     });
 
-    loop {
-        match rx.try_recv() {
-            Ok(msg) => {
-                println!("Got message: {:?}", msg);
-                let _ = v2_client
-                    .publish_value(
-                        "Vehicle.Cabin.HVAC.AmbientAirTemperature".to_owned(),
-                        v2_proto::Value {
-                            typed_value: Some(v2_proto::value::TypedValue::Float(
-                                vec_u8_to_f32_from_string(msg),
-                            )),
-                        },
-                    )
-                    .await;
-            }
-            Err(tokio::sync::mpsc::error::TryRecvError::Empty) => {
-                // No message available now, but sender is still active
-            }
-            Err(tokio::sync::mpsc::error::TryRecvError::Disconnected) => {
-                // Channel closed, no more messages possible
-                println!("Channel closed");
-            }
-        }
+    // Connect once the callbacks are set, as MQTT v3.x connection that is reconnected
+    // with backoff when it is lost
+    let options = mqtt::ConnectOptionsBuilder::new()
+        .automatic_reconnect(RECONNECT_MIN, RECONNECT_MAX)
+        .finalize();
+    let connecting = {
+        let mqtt_client = mqtt_client.clone();
+        let health = health.clone();
+        tokio::spawn(async move { connect(&mqtt_client, options, &health).await })
+    };
 
-        let result = v2_client
-            .get_value("Vehicle.Cabin.Light.AmbientLight.Row1.DriverSide.Color".to_owned())
-            .await;
-        match result {
-            Ok(option) => match option {
-                Some(datapoint) => {
-                    println!(
-                        "Vehicle.Cabin.Light.AmbientLight.Row1.DriverSide.Color: {:?}",
-                        datapoint.value
-                    );
-                    match datapoint.value {
-                        Some(value) => {
-                            let printable = DisplayDatapoint(value);
-                            //println!("Got value for Vehicle.Cabin.Light.AmbientLight.Row1.DriverSide.Color: {:?}", response);
-                            let msg = mqtt::Message::new(
-                                "compute/color",
-                                printable.to_string(),
-                                mqtt::QOS_1,
-                            );
-                            let _ = mqtt_client.publish(msg).await;
-                        }
-                        None => {
-                            let msg = mqtt::Message::new("compute/color", "0", mqtt::QOS_1);
-                            let _ = mqtt_client.publish(msg).await;
-                        }
-                    }
-                }
-                None => {
-                    println!("Vehicle.Cabin.Light.AmbientLight.Row1.DriverSide.Color not set");
-                }
-            },
-            Err(err) => {
-                println!(
-                    "Getting value for signal {:?} failed: {:?}",
-                    "Vehicle.Cabin.Light.AmbientLight.Row1.DriverSide.Color", err
-                );
+    tokio::select! {
+        _ = run(&mut v2_client, &mqtt_client, &mut rx, &mut provider, &mapping, &health, &metrics) => {}
+        result = shutdown::requested() => {
+            if let Err(err) = result {
+                warn!(?err, "Waiting for shutdown signals failed");
             }
         }
+    }
 
-        tokio::time::sleep(Duration::from_millis(500)).await;
+    // Graceful shutdown: stop receiving, forward what was already received and disconnect
+    connecting.abort();
+    if !topics.is_empty()
+        && let Err(err) = mqtt_client.unsubscribe_many(&topics).await
+    {
+        warn!(?topics, ?err, "Unsubscribing failed");
+    }
+    while let Ok(msg) = rx.try_recv() {
+        forward_received(
+            &mut v2_client,
+            &mqtt_client,
+            &mut provider,
+            &mapping,
+            &health,
+            &metrics,
+            msg,
+        )
+        .await;
+    }
+    if let Err(err) = mqtt_client.disconnect(None).await {
+        warn!(?err, "Disconnecting from the MQTT server failed");
     }
+    info!("Shutdown complete");
+    telemetry.shutdown();
 }
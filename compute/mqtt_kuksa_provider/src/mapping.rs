@@ -0,0 +1,346 @@
+// Copyright (c) 2025 Eclipse Foundation and others.
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::codec::Selector;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+
+const DEFAULT_QOS: i32 = 1;
+const DEFAULT_DEAD_LETTER_TOPIC: &str = "carmate/errors";
+const DEFAULT_ACK_TIMEOUT_MS: u64 = 2000;
+
+// VSS datatype of a mapped signal
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DataType {
+    #[serde(rename = "string")]
+    String,
+    #[serde(rename = "boolean")]
+    Boolean,
+    #[serde(rename = "int8")]
+    Int8,
+    #[serde(rename = "int16")]
+    Int16,
+    #[serde(rename = "int32")]
+    Int32,
+    #[serde(rename = "int64")]
+    Int64,
+    #[serde(rename = "uint8")]
+    Uint8,
+    #[serde(rename = "uint16")]
+    Uint16,
+    #[serde(rename = "uint32")]
+    Uint32,
+    #[serde(rename = "uint64")]
+    Uint64,
+    #[serde(rename = "float")]
+    Float,
+    #[serde(rename = "double")]
+    Double,
+    #[serde(rename = "string[]")]
+    StringArray,
+    #[serde(rename = "boolean[]")]
+    BooleanArray,
+    #[serde(rename = "int8[]")]
+    Int8Array,
+    #[serde(rename = "int16[]")]
+    Int16Array,
+    #[serde(rename = "int32[]")]
+    Int32Array,
+    #[serde(rename = "int64[]")]
+    Int64Array,
+    #[serde(rename = "uint8[]")]
+    Uint8Array,
+    #[serde(rename = "uint16[]")]
+    Uint16Array,
+    #[serde(rename = "uint32[]")]
+    Uint32Array,
+    #[serde(rename = "uint64[]")]
+    Uint64Array,
+    #[serde(rename = "float[]")]
+    FloatArray,
+    #[serde(rename = "double[]")]
+    DoubleArray,
+}
+
+impl DataType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            DataType::String => "string",
+            DataType::Boolean => "boolean",
+            DataType::Int8 => "int8",
+            DataType::Int16 => "int16",
+            DataType::Int32 => "int32",
+            DataType::Int64 => "int64",
+            DataType::Uint8 => "uint8",
+            DataType::Uint16 => "uint16",
+            DataType::Uint32 => "uint32",
+            DataType::Uint64 => "uint64",
+            DataType::Float => "float",
+            DataType::Double => "double",
+            DataType::StringArray => "string[]",
+            DataType::BooleanArray => "boolean[]",
+            DataType::Int8Array => "int8[]",
+            DataType::Int16Array => "int16[]",
+            DataType::Int32Array => "int32[]",
+            DataType::Int64Array => "int64[]",
+            DataType::Uint8Array => "uint8[]",
+            DataType::Uint16Array => "uint16[]",
+            DataType::Uint32Array => "uint32[]",
+            DataType::Uint64Array => "uint64[]",
+            DataType::FloatArray => "float[]",
+            DataType::DoubleArray => "double[]",
+        }
+    }
+
+    // Datatype of the elements of an array type
+    pub(crate) fn element(&self) -> Option<DataType> {
+        match self {
+            DataType::StringArray => Some(DataType::String),
+            DataType::BooleanArray => Some(DataType::Boolean),
+            DataType::Int8Array => Some(DataType::Int8),
+            DataType::Int16Array => Some(DataType::Int16),
+            DataType::Int32Array => Some(DataType::Int32),
+            DataType::Int64Array => Some(DataType::Int64),
+            DataType::Uint8Array => Some(DataType::Uint8),
+            DataType::Uint16Array => Some(DataType::Uint16),
+            DataType::Uint32Array => Some(DataType::Uint32),
+            DataType::Uint64Array => Some(DataType::Uint64),
+            DataType::FloatArray => Some(DataType::Float),
+            DataType::DoubleArray => Some(DataType::Double),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for DataType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// Encoding of the MQTT payload
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Codec {
+    // Human readable value, arrays as comma separated list
+    #[default]
+    Text,
+    // JSON document
+    Json,
+    // CBOR document (RFC 8949)
+    Cbor,
+    // MessagePack document
+    Msgpack,
+    // Little-endian numbers of the size of the datatype, arrays as consecutive elements
+    BinaryLe,
+    // Big-endian numbers of the size of the datatype, arrays as consecutive elements
+    BinaryBe,
+    // Color of the MxChip RGB LED as quoted hex code, e.g. 'FF0000', strings only
+    Rgb,
+}
+
+impl Codec {
+    // Whether the payload is a document the value can be selected from
+    pub(crate) fn is_document(&self) -> bool {
+        matches!(self, Codec::Json | Codec::Cbor | Codec::Msgpack)
+    }
+
+    fn is_binary(&self) -> bool {
+        matches!(self, Codec::BinaryLe | Codec::BinaryBe)
+    }
+}
+
+fn default_qos() -> i32 {
+    DEFAULT_QOS
+}
+
+fn default_ack_timeout_ms() -> u64 {
+    DEFAULT_ACK_TIMEOUT_MS
+}
+
+fn default_dead_letter_topic() -> String {
+    DEFAULT_DEAD_LETTER_TOPIC.to_owned()
+}
+
+// MQTT topic whose messages are published to a VSS signal in Kuksa
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct InboundRoute {
+    pub topic: String,
+    pub path: String,
+    pub datatype: DataType,
+    // QoS of the subscription
+    #[serde(default = "default_qos")]
+    pub qos: i32,
+    #[serde(default)]
+    pub codec: Codec,
+    // Documents only: location of the value, e.g. "$.temp", the whole document if unset
+    pub selector: Option<Selector>,
+}
+
+// VSS actuator in Kuksa whose target values are published to an MQTT topic
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct OutboundRoute {
+    pub path: String,
+    pub topic: String,
+    pub datatype: DataType,
+    #[serde(default = "default_qos")]
+    pub qos: i32,
+    // Let the broker keep the last value for new subscribers
+    #[serde(default)]
+    pub retain: bool,
+    #[serde(default)]
+    pub codec: Codec,
+    // Documents only: object fields the value is wrapped in, e.g. "$.color.rgb"
+    pub selector: Option<Selector>,
+    // Topic on which the MCU confirms an actuation with the value it applied,
+    // decoded like the target value. Without it the broker accepting the message counts.
+    pub ack_topic: Option<String>,
+    #[serde(default = "default_ack_timeout_ms")]
+    pub ack_timeout_ms: u64,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Mapping {
+    #[serde(default)]
+    pub inbound: Vec<InboundRoute>,
+    #[serde(default)]
+    pub outbound: Vec<OutboundRoute>,
+    // Topic on which inbound messages with an invalid payload are published instead
+    #[serde(default = "default_dead_letter_topic")]
+    pub dead_letter_topic: String,
+}
+
+fn check_route(topic: &str, path: &str, qos: i32) -> Result<(), String> {
+    if path.is_empty() {
+        return Err(format!(
+            "Route of MQTT topic {topic:?} has an empty VSS path"
+        ));
+    }
+    if topic.is_empty() || topic.contains(['+', '#']) {
+        return Err(format!(
+            "MQTT topic {topic:?} of {path} must be a non-empty topic without wildcards"
+        ));
+    }
+    if !(0..=2).contains(&qos) {
+        return Err(format!("QoS {qos} of MQTT topic {topic} must be 0, 1 or 2"));
+    }
+    Ok(())
+}
+
+fn check_codec(
+    topic: &str,
+    datatype: DataType,
+    codec: Codec,
+    selector: Option<&Selector>,
+) -> Result<(), String> {
+    if selector.is_some() && !codec.is_document() {
+        return Err(format!(
+            "Selector of MQTT topic {topic} requires the json, cbor or msgpack codec"
+        ));
+    }
+    if codec.is_binary() && matches!(datatype, DataType::String | DataType::StringArray) {
+        return Err(format!(
+            "Datatype {datatype} of MQTT topic {topic} has no binary encoding"
+        ));
+    }
+    if codec == Codec::Rgb && datatype != DataType::String {
+        return Err(format!(
+            "The rgb codec of MQTT topic {topic} requires the datatype string, not {datatype}"
+        ));
+    }
+    Ok(())
+}
+
+// Load and validate the route mapping file
+pub(crate) fn load(path: &Path) -> Result<Mapping, Box<dyn std::error::Error>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|err| format!("Cannot read mapping file {}: {err}", path.display()))?;
+    let file: Mapping = toml::from_str(&content)
+        .map_err(|err| format!("Cannot parse mapping file {}: {err}", path.display()))?;
+
+    if file.inbound.is_empty() && file.outbound.is_empty() {
+        return Err(format!("Mapping file {} contains no routes", path.display()).into());
+    }
+
+    let mut topics = HashSet::new();
+    for route in &file.inbound {
+        check_route(&route.topic, &route.path, route.qos)?;
+        check_codec(
+            &route.topic,
+            route.datatype,
+            route.codec,
+            route.selector.as_ref(),
+        )?;
+        if !topics.insert(route.topic.as_str()) {
+            return Err(format!("MQTT topic {} is mapped more than once", route.topic).into());
+        }
+    }
+
+    let mut actuators = HashSet::new();
+    for route in &file.outbound {
+        check_route(&route.topic, &route.path, route.qos)?;
+        check_codec(
+            &route.topic,
+            route.datatype,
+            route.codec,
+            route.selector.as_ref(),
+        )?;
+        if let Some(selector) = &route.selector
+            && !selector.is_fields()
+        {
+            return Err(format!(
+                "Selector {selector} of MQTT topic {} may only contain object fields",
+                route.topic
+            )
+            .into());
+        }
+        // One provider forwards the actuations of an actuator to one topic
+        if !actuators.insert(route.path.as_str()) {
+            return Err(format!("VSS actuator {} is mapped more than once", route.path).into());
+        }
+        if let Some(ack_topic) = &route.ack_topic {
+            // The MCU confirms on its own topic, the bridge would otherwise confirm itself
+            if ack_topic.is_empty() || ack_topic.contains(['+', '#']) || *ack_topic == route.topic {
+                return Err(format!(
+                    "Confirmation topic {ack_topic:?} of {} must be a non-empty topic without wildcards other than {}",
+                    route.path, route.topic
+                )
+                .into());
+            }
+            if !topics.insert(ack_topic.as_str()) {
+                return Err(format!("MQTT topic {ack_topic} is mapped more than once").into());
+            }
+        }
+    }
+
+    let topic = &file.dead_letter_topic;
+    if topic.is_empty() || topic.contains(['+', '#']) || topics.contains(topic.as_str()) {
+        return Err(format!(
+            "Dead-letter topic {topic:?} must be a non-empty topic without wildcards that is not subscribed"
+        )
+        .into());
+    }
+
+    Ok(file)
+}
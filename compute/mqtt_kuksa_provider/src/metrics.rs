@@ -0,0 +1,129 @@
+// Copyright (c) 2025 Eclipse Foundation and others.
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::health::Health;
+use carmate_service::http::Response;
+use carmate_service::metrics::{self, Counters, Histograms};
+use kuksa_rust_sdk::kuksa::common::ClientError;
+
+// gRPC status code of a Kuksa client error, errors raised by the client itself count as unknown
+fn error_code(err: &ClientError) -> tonic::Code {
+    match err {
+        ClientError::Connection(_) => tonic::Code::Unavailable,
+        ClientError::Status(status) => status.code(),
+        ClientError::Function(_) => tonic::Code::Unknown,
+    }
+}
+
+// Counters and histograms of the bridge, exported in the Prometheus text format
+pub(crate) struct Metrics {
+    mqtt_received: Counters,
+    mqtt_published: Counters,
+    invalid_messages: Counters,
+    kuksa_published: Counters,
+    grpc_errors: Counters,
+    latency: Histograms,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Metrics {
+            mqtt_received: Counters::new(
+                "mkp_mqtt_messages_received_total",
+                "Messages received from the MQTT broker",
+                &["topic"],
+            ),
+            mqtt_published: Counters::new(
+                "mkp_mqtt_messages_published_total",
+                "Messages published to the MQTT broker",
+                &["topic"],
+            ),
+            invalid_messages: Counters::new(
+                "mkp_invalid_messages_total",
+                "MQTT messages rejected because their payload is not a valid value of the signal",
+                &["topic", "reason"],
+            ),
+            kuksa_published: Counters::new(
+                "mkp_kuksa_messages_published_total",
+                "Values published to the Kuksa databroker",
+                &["signal"],
+            ),
+            grpc_errors: Counters::new(
+                "mkp_grpc_errors_total",
+                "Failed requests to the Kuksa databroker by gRPC status code",
+                &["code"],
+            ),
+            latency: Histograms::new(
+                "mkp_publish_latency_seconds",
+                "Time from receiving an MQTT message to publishing its value to the Kuksa databroker",
+                &["signal"],
+            ),
+        }
+    }
+}
+
+impl Metrics {
+    // A message was received from the MQTT broker
+    pub(crate) fn mqtt_received(&self, topic: &str) {
+        self.mqtt_received.increment(&[topic]);
+    }
+
+    // A message was published to the MQTT broker
+    pub(crate) fn mqtt_published(&self, topic: &str) {
+        self.mqtt_published.increment(&[topic]);
+    }
+
+    // The payload of an MQTT message was rejected, e.g. as "invalid-value"
+    pub(crate) fn invalid_message(&self, topic: &str, reason: &'static str) {
+        self.invalid_messages.increment(&[topic, reason]);
+    }
+
+    // A value was published to the databroker
+    pub(crate) fn kuksa_published(&self, path: &str) {
+        self.kuksa_published.increment(&[path]);
+    }
+
+    // A databroker request failed
+    pub(crate) fn grpc_error(&self, err: &ClientError) {
+        self.grpc_status(error_code(err));
+    }
+
+    // A databroker request or the provider stream failed with the given status code
+    pub(crate) fn grpc_status(&self, code: tonic::Code) {
+        self.grpc_errors.increment(&[&format!("{code:?}")]);
+    }
+
+    // Time from receiving an MQTT message to publishing its value to the databroker
+    pub(crate) fn latency(&self, path: &str, seconds: f64) {
+        self.latency.observe(&[path], seconds);
+    }
+
+    // The /metrics endpoint
+    pub(crate) fn response(&self, health: &Health) -> Response {
+        let mut out = String::new();
+        self.mqtt_received.render(&mut out);
+        self.mqtt_published.render(&mut out);
+        self.invalid_messages.render(&mut out);
+        self.kuksa_published.render(&mut out);
+        self.grpc_errors.render(&mut out);
+        self.latency.render(&mut out);
+        metrics::render_reconnects(&mut out, "mkp_reconnects_total", &health.connections());
+        metrics::response(out)
+    }
+}
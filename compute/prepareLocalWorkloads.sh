@@ -21,11 +21,10 @@
 # *******************************************************************************
 
 ## MQTT KUKSA PROVIDER #####################################
-cd mqtt_kuksa_provider
 # needs to run sudo because ank-agent runs as root user and 
 # pulls from root container image storage
-sudo podman build -t localhost/mqtt-kuksa-provider:latest .
-cd ..
+# built from compute/ to include the shared carmate_service crate
+sudo podman build -t localhost/mqtt-kuksa-provider:latest -f mqtt_kuksa_provider/Dockerfile .
 
 ## IO CARMATE ####################################
 cd car_mate_io
@@ -44,16 +43,14 @@ cd ..
 #############################################################
 
 ## VEHICLE DATA ACCESSOR ####################################
-cd vehicle_data_accessor
 # needs to run sudo because ank-agent runs as root user and 
 # pulls from root container image storage
-sudo podman build -t localhost/vehicle-data-accessor:latest .
-cd ..
+# built from compute/ to include the shared carmate_service crate
+sudo podman build -t localhost/vehicle-data-accessor:latest -f vehicle_data_accessor/Dockerfile .
 
 ## CARLA PROVIDER ###########################################
-cd carla_provider
 # needs to run sudo because ank-agent runs as root user and
 # pulls from root container image storage
-sudo podman build -t localhost/carla-provider:latest .
-cd ..
+# built from compute/ to include the shared carmate_service crate
+sudo podman build -t localhost/carla-provider:latest -f carla_provider/Dockerfile .
 #############################################################
\ No newline at end of file
@@ -0,0 +1,87 @@
+// Copyright (c) 2025 Eclipse Foundation and others.
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use std::time::{Duration, Instant};
+
+// Playback position within a recording in milliseconds, advancing with the playback speed
+pub(crate) struct Clock {
+    // Position at the anchor instant
+    position: f64,
+    anchor: Instant,
+    speed: f64,
+    paused: bool,
+}
+
+impl Clock {
+    pub(crate) fn new(speed: f64) -> Self {
+        Clock {
+            position: 0.0,
+            anchor: Instant::now(),
+            speed,
+            paused: false,
+        }
+    }
+
+    pub(crate) fn position(&self) -> f64 {
+        if self.paused {
+            self.position
+        } else {
+            self.position + self.anchor.elapsed().as_secs_f64() * 1000.0 * self.speed
+        }
+    }
+
+    // Real time until the playback reaches the given position, None while paused
+    pub(crate) fn until(&self, position: f64) -> Option<Duration> {
+        if self.paused {
+            return None;
+        }
+        let remaining = (position - self.position()).max(0.0) / self.speed;
+        Some(Duration::from_secs_f64(remaining / 1000.0))
+    }
+
+    pub(crate) fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    pub(crate) fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub(crate) fn pause(&mut self) {
+        self.position = self.position();
+        self.paused = true;
+    }
+
+    pub(crate) fn resume(&mut self) {
+        if self.paused {
+            self.anchor = Instant::now();
+            self.paused = false;
+        }
+    }
+
+    pub(crate) fn seek(&mut self, position: f64) {
+        self.position = position;
+        self.anchor = Instant::now();
+    }
+
+    pub(crate) fn set_speed(&mut self, speed: f64) {
+        self.seek(self.position());
+        self.speed = speed;
+    }
+}
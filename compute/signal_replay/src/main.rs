@@ -0,0 +1,221 @@
+// Copyright (c) 2025 Eclipse Foundation and others.
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+mod clock;
+mod recording;
+
+use clap::Parser;
+use clock::Clock;
+use kuksa_rust_sdk::kuksa::common::ClientTraitV2;
+use kuksa_rust_sdk::kuksa::val::v2::KuksaClientV2;
+use recording::Record;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::sync::mpsc;
+use tonic::transport::Uri;
+
+// Feeds recordings of the vehicle data accessor back into the Kuksa Databroker
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Recording files, or directories containing signals-*.jsonl recordings
+    #[clap(required = true)]
+    recordings: Vec<PathBuf>,
+    /// URL of the Kuksa Databroker
+    #[clap(
+        long,
+        env = "REPLAY_KUKSA_URL",
+        default_value = "http://localhost:55555"
+    )]
+    kuksa_url: String,
+    /// Playback speed, e.g. 10 for ten times faster than real time
+    #[clap(long, default_value_t = 1.0)]
+    speed: f64,
+    /// Start this many seconds into the recording
+    #[clap(long, default_value_t = 0.0)]
+    seek: f64,
+    /// Start paused, e.g. to seek interactively first
+    #[clap(long)]
+    paused: bool,
+    /// Only replay these VSS paths, comma separated
+    #[clap(long, value_delimiter = ',')]
+    only: Vec<String>,
+}
+
+// Playback commands read from stdin
+#[derive(Debug, Clone, Copy)]
+enum Command {
+    Pause,
+    Resume,
+    // Position in seconds from the start of the recording
+    Seek(f64),
+    Speed(f64),
+    Status,
+    Quit,
+}
+
+const HELP: &str = "Commands: p(ause), r(esume), s(eek) <seconds>, speed <factor>, i(nfo), q(uit)";
+
+fn parse_command(line: &str) -> Result<Command, String> {
+    let mut words = line.split_whitespace();
+    let command = words.next().unwrap_or_default();
+    let number = |argument: Option<&str>| -> Result<f64, String> {
+        argument
+            .ok_or_else(|| format!("{command} needs a number"))?
+            .parse()
+            .map_err(|err| format!("Invalid number for {command}: {err}"))
+    };
+    match command {
+        "p" | "pause" => Ok(Command::Pause),
+        "r" | "resume" => Ok(Command::Resume),
+        "s" | "seek" => Ok(Command::Seek(number(words.next())?)),
+        "speed" => Ok(Command::Speed(number(words.next())?)),
+        "i" | "info" => Ok(Command::Status),
+        "q" | "quit" => Ok(Command::Quit),
+        _ => Err(HELP.to_owned()),
+    }
+}
+
+// Forward commands from stdin until it is closed
+async fn read_commands(sender: mpsc::Sender<Command>) {
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        match parse_command(&line) {
+            Ok(command) => {
+                if sender.send(command).await.is_err() {
+                    return;
+                }
+            }
+            Err(message) => println!("{message}"),
+        }
+    }
+}
+
+async fn publish(client: &mut KuksaClientV2, record: &Record) {
+    match client
+        .publish_value(record.path.clone(), record.value.clone())
+        .await
+    {
+        Ok(()) => println!("{} = {:?}", record.path, record.value.typed_value),
+        Err(err) => println!("Publishing {} failed: {:?}", record.path, err),
+    }
+}
+
+// Publish the latest value of every signal before the given index, so the
+// databroker holds the state of the recording at that position
+async fn restore(client: &mut KuksaClientV2, records: &[Record], index: usize) {
+    let mut latest: HashMap<&str, &Record> = HashMap::new();
+    for record in &records[..index] {
+        latest.insert(record.path.as_str(), record);
+    }
+    for record in latest.into_values() {
+        publish(client, record).await;
+    }
+}
+
+// Wait for the given time, or forever while paused
+async fn wait(duration: Option<Duration>) {
+    match duration {
+        Some(duration) => tokio::time::sleep(duration).await,
+        None => std::future::pending().await,
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+    if args.speed <= 0.0 {
+        return Err("Speed must be greater than 0".into());
+    }
+    let kuksa_uri: Uri = args
+        .kuksa_url
+        .parse()
+        .map_err(|err| format!("Invalid Kuksa URL {:?}: {err}", args.kuksa_url))?;
+
+    let records = recording::load(&args.recordings, &args.only)?;
+    let (Some(first), Some(last)) = (records.first(), records.last()) else {
+        return Err("The recordings contain no values to replay".into());
+    };
+    let start = first.timestamp;
+    // Position of a record in milliseconds from the start of the recording
+    let position = |record: &Record| (record.timestamp - start) as f64;
+    let duration = position(last);
+    println!(
+        "Replaying {} changes spanning {:.1} s at {}x",
+        records.len(),
+        duration / 1000.0,
+        args.speed
+    );
+    println!("{HELP}");
+
+    let mut client = KuksaClientV2::new(kuksa_uri);
+    let (sender, mut commands) = mpsc::channel(8);
+    tokio::spawn(read_commands(sender));
+    let mut interactive = true;
+
+    let mut clock = Clock::new(args.speed);
+    let mut index = 0;
+    if args.seek > 0.0 {
+        let target = (args.seek * 1000.0).min(duration);
+        index = records.partition_point(|record| position(record) < target);
+        restore(&mut client, &records, index).await;
+        clock.seek(target);
+    }
+    if args.paused {
+        clock.pause();
+    }
+
+    while let Some(record) = records.get(index) {
+        tokio::select! {
+            _ = wait(clock.until(position(record))) => {
+                publish(&mut client, record).await;
+                index += 1;
+            }
+            command = commands.recv(), if interactive => match command {
+                Some(Command::Pause) => clock.pause(),
+                Some(Command::Resume) => clock.resume(),
+                Some(Command::Seek(seconds)) => {
+                    let target = (seconds * 1000.0).clamp(0.0, duration);
+                    index = records.partition_point(|record| position(record) < target);
+                    restore(&mut client, &records, index).await;
+                    clock.seek(target);
+                }
+                Some(Command::Speed(speed)) if speed > 0.0 => clock.set_speed(speed),
+                Some(Command::Speed(_)) => println!("Speed must be greater than 0"),
+                Some(Command::Status) => println!(
+                    "{:.1} s of {:.1} s at {}x{}",
+                    clock.position() / 1000.0,
+                    duration / 1000.0,
+                    clock.speed(),
+                    if clock.is_paused() { ", paused" } else { "" }
+                ),
+                Some(Command::Quit) => return Ok(()),
+                None => {
+                    // Stdin closed, e.g. when running in the background, nobody can resume
+                    interactive = false;
+                    clock.resume();
+                }
+            },
+        }
+    }
+    println!("Replay finished");
+    Ok(())
+}
@@ -0,0 +1,154 @@
+// Copyright (c) 2025 Eclipse Foundation and others.
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use kuksa_rust_sdk::v2_proto;
+use serde::{Deserialize, de::DeserializeOwned};
+use std::path::{Path, PathBuf};
+
+// Recordings of the vehicle data accessor are named signals-<start in ms>.jsonl
+const FILE_PREFIX: &str = "signals-";
+const FILE_SUFFIX: &str = ".jsonl";
+
+// One line of a recording, as written by the vehicle data accessor
+#[derive(Deserialize, Debug)]
+struct RecordLine {
+    timestamp: i64,
+    path: String,
+    datatype: Option<String>,
+    value: serde_json::Value,
+}
+
+// A recorded signal change, ready to be published
+#[derive(Debug, Clone)]
+pub(crate) struct Record {
+    // Milliseconds since the Unix epoch
+    pub timestamp: i64,
+    pub path: String,
+    pub value: v2_proto::Value,
+}
+
+// Recording files given directly or found in a directory, in chronological order
+fn recording_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>, String> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            let entries = std::fs::read_dir(path)
+                .map_err(|err| format!("Cannot read directory {}: {err}", path.display()))?;
+            let mut found: Vec<PathBuf> = entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|file| {
+                    file.file_name()
+                        .map(|name| name.to_string_lossy())
+                        .is_some_and(|name| {
+                            name.starts_with(FILE_PREFIX) && name.ends_with(FILE_SUFFIX)
+                        })
+                })
+                .collect();
+            found.sort();
+            files.extend(found);
+        } else {
+            files.push(path.clone());
+        }
+    }
+    Ok(files)
+}
+
+// Load all recorded changes, sorted by timestamp, optionally limited to some VSS paths
+pub(crate) fn load(paths: &[PathBuf], only: &[String]) -> Result<Vec<Record>, String> {
+    let mut records = Vec::new();
+    for file in recording_files(paths)? {
+        load_file(&file, only, &mut records)?;
+    }
+    // Stable, so changes with the same timestamp keep their recorded order
+    records.sort_by_key(|record| record.timestamp);
+    Ok(records)
+}
+
+fn load_file(file: &Path, only: &[String], records: &mut Vec<Record>) -> Result<(), String> {
+    let content = std::fs::read_to_string(file)
+        .map_err(|err| format!("Cannot read recording {}: {err}", file.display()))?;
+    for (number, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let line: RecordLine = serde_json::from_str(line)
+            .map_err(|err| format!("{}:{}: {err}", file.display(), number + 1))?;
+        if !only.is_empty() && !only.contains(&line.path) {
+            continue;
+        }
+        let Some(datatype) = line.datatype else {
+            // Nothing to publish for a change without value
+            continue;
+        };
+        let value = to_value(&datatype, &line.value)
+            .map_err(|err| format!("{}:{}: {err}", file.display(), number + 1))?;
+        records.push(Record {
+            timestamp: line.timestamp,
+            path: line.path,
+            value,
+        });
+    }
+    Ok(())
+}
+
+fn parse<T: DeserializeOwned>(json: &serde_json::Value) -> Result<T, String> {
+    serde_json::from_value(json.clone()).map_err(|err| format!("Invalid value {json}: {err}"))
+}
+
+// Convert a recorded JSON value back into a databroker value of its recorded VSS datatype
+fn to_value(datatype: &str, json: &serde_json::Value) -> Result<v2_proto::Value, String> {
+    let typed_value = match datatype {
+        "boolean" => v2_proto::value::TypedValue::Bool(parse(json)?),
+        "int32" => v2_proto::value::TypedValue::Int32(parse(json)?),
+        "int64" => v2_proto::value::TypedValue::Int64(parse(json)?),
+        "uint32" => v2_proto::value::TypedValue::Uint32(parse(json)?),
+        "uint64" => v2_proto::value::TypedValue::Uint64(parse(json)?),
+        "float" => v2_proto::value::TypedValue::Float(parse(json)?),
+        "double" => v2_proto::value::TypedValue::Double(parse(json)?),
+        "string" => v2_proto::value::TypedValue::String(parse(json)?),
+        "string[]" => v2_proto::value::TypedValue::StringArray(v2_proto::StringArray {
+            values: parse(json)?,
+        }),
+        "boolean[]" => v2_proto::value::TypedValue::BoolArray(v2_proto::BoolArray {
+            values: parse(json)?,
+        }),
+        "int32[]" => v2_proto::value::TypedValue::Int32Array(v2_proto::Int32Array {
+            values: parse(json)?,
+        }),
+        "int64[]" => v2_proto::value::TypedValue::Int64Array(v2_proto::Int64Array {
+            values: parse(json)?,
+        }),
+        "uint32[]" => v2_proto::value::TypedValue::Uint32Array(v2_proto::Uint32Array {
+            values: parse(json)?,
+        }),
+        "uint64[]" => v2_proto::value::TypedValue::Uint64Array(v2_proto::Uint64Array {
+            values: parse(json)?,
+        }),
+        "float[]" => v2_proto::value::TypedValue::FloatArray(v2_proto::FloatArray {
+            values: parse(json)?,
+        }),
+        "double[]" => v2_proto::value::TypedValue::DoubleArray(v2_proto::DoubleArray {
+            values: parse(json)?,
+        }),
+        _ => return Err(format!("Unsupported datatype {datatype}")),
+    };
+    Ok(v2_proto::Value {
+        typed_value: Some(typed_value),
+    })
+}
@@ -0,0 +1,306 @@
+// Copyright (c) 2025 Eclipse Foundation and others.
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::publisher::OfflinePolicy;
+use crate::recorder::RecorderOptions;
+use carmate_service::telemetry::LogFormat;
+use carmate_service::zenoh_options::{ZenohMode, ZenohOptions};
+use clap::Parser;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use tonic::transport::Uri;
+
+const DEFAULT_KUKSA_URL: &str = "http://localhost:55555";
+const DEFAULT_AUTHORITY: &str = "vehicledataaccessor";
+const DEFAULT_UE_ID: u32 = 0;
+const DEFAULT_UE_VERSION: u8 = 2;
+const DEFAULT_MAPPING: &str = "signals.toml";
+const DEFAULT_LOG_LEVEL: &str = "info";
+const DEFAULT_RECORD_MAX_FILE_MB: u64 = 10;
+const DEFAULT_RECORD_MAX_FILES: usize = 10;
+const DEFAULT_HEALTH_PORT: u16 = 9101;
+const DEFAULT_HEALTH_INTERVAL_MS: u64 = 5000;
+
+// Command line flags, each one can also be set by an environment variable.
+// Precedence: command line > environment > config file > default
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Configuration file (TOML) with the long flags as keys in snake_case, e.g. kuksa_url
+    #[clap(long, env = "VDA_CONFIG")]
+    config: Option<PathBuf>,
+    /// URL of the Kuksa Databroker [default: http://localhost:55555]
+    #[clap(long, env = "VDA_KUKSA_URL")]
+    kuksa_url: Option<String>,
+    /// uProtocol authority of this entity [default: vehicledataaccessor]
+    #[clap(long, env = "VDA_AUTHORITY")]
+    authority: Option<String>,
+    /// uProtocol entity id [default: 0]
+    #[clap(long, env = "VDA_UE_ID")]
+    ue_id: Option<u32>,
+    /// uProtocol entity major version [default: 2]
+    #[clap(long, env = "VDA_UE_VERSION")]
+    ue_version: Option<u8>,
+    /// Zenoh configuration file (JSON5), the Zenoh flags below override its settings
+    #[clap(long, env = "VDA_ZENOH_CONFIG")]
+    zenoh_config: Option<PathBuf>,
+    /// Zenoh mode
+    #[clap(long, value_enum, env = "VDA_ZENOH_MODE")]
+    zenoh_mode: Option<ZenohMode>,
+    /// Zenoh router to connect to, "host" (tcp port 7447) or a full endpoint like "tls/host:7447"
+    #[clap(long, env = "VDA_ROUTER")]
+    router: Option<String>,
+    /// Zenoh endpoints to connect to, comma separated
+    #[clap(long, env = "VDA_CONNECT", value_delimiter = ',')]
+    connect: Vec<String>,
+    /// Zenoh endpoints to listen on, comma separated
+    #[clap(long, env = "VDA_LISTEN", value_delimiter = ',')]
+    listen: Vec<String>,
+    /// Root CA certificate for Zenoh TLS links
+    #[clap(long, env = "VDA_TLS_ROOT_CA")]
+    tls_root_ca: Option<PathBuf>,
+    /// Certificate for Zenoh TLS links (mutual TLS)
+    #[clap(long, env = "VDA_TLS_CERTIFICATE")]
+    tls_certificate: Option<PathBuf>,
+    /// Private key for Zenoh TLS links (mutual TLS)
+    #[clap(long, env = "VDA_TLS_PRIVATE_KEY")]
+    tls_private_key: Option<PathBuf>,
+    /// Signal mapping file [default: signals.toml]
+    #[clap(long, env = "VDA_MAPPING")]
+    mapping: Option<PathBuf>,
+    /// Log filter, e.g. "info" or "debug,zenoh=warn", RUST_LOG takes precedence [default: info]
+    #[clap(long, env = "VDA_LOG_LEVEL")]
+    log_level: Option<String>,
+    /// Log output format [default: text]
+    #[clap(long, value_enum, env = "VDA_LOG_FORMAT")]
+    log_format: Option<LogFormat>,
+    /// OpenTelemetry collector to export spans to over OTLP/gRPC, e.g. http://localhost:4317
+    #[clap(long, env = "VDA_OTLP_ENDPOINT")]
+    otlp_endpoint: Option<String>,
+    /// Poll the databroker instead of subscribing, for brokers without subscription support
+    #[clap(long, env = "VDA_POLL", num_args = 0..=1, default_missing_value = "true")]
+    poll: Option<bool>,
+    /// What to do with messages while Zenoh is disconnected [default: keep-latest]
+    #[clap(long, value_enum, env = "VDA_OFFLINE_POLICY")]
+    offline_policy: Option<OfflinePolicy>,
+    /// Record all signal changes as JSON lines into this directory
+    #[clap(long, env = "VDA_RECORD_DIR")]
+    record_dir: Option<PathBuf>,
+    /// Size of one recording file in MB before a new one is started [default: 10]
+    #[clap(long, env = "VDA_RECORD_MAX_FILE_MB")]
+    record_max_file_mb: Option<u64>,
+    /// Number of recording files to keep, older ones are deleted [default: 10]
+    #[clap(long, env = "VDA_RECORD_MAX_FILES")]
+    record_max_files: Option<usize>,
+    /// Port of the HTTP /healthz, /readyz and /metrics endpoints, 0 disables them [default: 9101]
+    #[clap(long, env = "VDA_HEALTH_PORT")]
+    health_port: Option<u16>,
+    /// Interval of the uProtocol health message, 0 disables it [default: 5000]
+    #[clap(long, env = "VDA_HEALTH_INTERVAL_MS")]
+    health_interval_ms: Option<u64>,
+}
+
+// Keys in snake_case like those of the mapping files
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+struct FileConfig {
+    kuksa_url: Option<String>,
+    authority: Option<String>,
+    ue_id: Option<u32>,
+    ue_version: Option<u8>,
+    zenoh_config: Option<PathBuf>,
+    zenoh_mode: Option<ZenohMode>,
+    router: Option<String>,
+    connect: Option<Vec<String>>,
+    listen: Option<Vec<String>>,
+    tls_root_ca: Option<PathBuf>,
+    tls_certificate: Option<PathBuf>,
+    tls_private_key: Option<PathBuf>,
+    mapping: Option<PathBuf>,
+    log_level: Option<String>,
+    log_format: Option<LogFormat>,
+    otlp_endpoint: Option<String>,
+    poll: Option<bool>,
+    offline_policy: Option<OfflinePolicy>,
+    record_dir: Option<PathBuf>,
+    record_max_file_mb: Option<u64>,
+    record_max_files: Option<usize>,
+    health_port: Option<u16>,
+    health_interval_ms: Option<u64>,
+}
+
+// Validated configuration of the vehicle data accessor
+#[derive(Debug)]
+pub(crate) struct Config {
+    pub kuksa_uri: Uri,
+    pub authority: String,
+    pub ue_id: u32,
+    pub ue_version: u8,
+    pub zenoh: ZenohOptions,
+    pub mapping: PathBuf,
+    pub log_level: String,
+    pub log_format: LogFormat,
+    pub otlp_endpoint: Option<String>,
+    pub poll: bool,
+    pub offline_policy: OfflinePolicy,
+    pub record: Option<RecorderOptions>,
+    pub health_port: u16,
+    pub health_interval_ms: u64,
+}
+
+fn load_file(path: &Path) -> Result<FileConfig, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|err| format!("Cannot read config file {}: {err}", path.display()))?;
+    toml::from_str(&content)
+        .map_err(|err| format!("Cannot parse config file {}: {err}", path.display()))
+}
+
+// Lists given on the command line or in the environment replace those of the config file
+fn non_empty_or(args: Vec<String>, file: Option<Vec<String>>) -> Vec<String> {
+    if args.is_empty() {
+        file.unwrap_or_default()
+    } else {
+        args
+    }
+}
+
+// Parse command line, environment and config file into a validated configuration
+pub(crate) fn load() -> Result<Config, Box<dyn std::error::Error>> {
+    let args = Args::parse();
+    let file = match &args.config {
+        Some(path) => load_file(path)?,
+        None => FileConfig::default(),
+    };
+
+    let kuksa_url = args
+        .kuksa_url
+        .or(file.kuksa_url)
+        .unwrap_or_else(|| DEFAULT_KUKSA_URL.to_owned());
+    let kuksa_uri: Uri = kuksa_url
+        .parse()
+        .map_err(|err| format!("Invalid Kuksa URL {kuksa_url:?}: {err}"))?;
+    if !matches!(kuksa_uri.scheme_str(), Some("http") | Some("https")) {
+        return Err(format!("Kuksa URL {kuksa_url:?} must start with http:// or https://").into());
+    }
+
+    let authority = args
+        .authority
+        .or(file.authority)
+        .unwrap_or_else(|| DEFAULT_AUTHORITY.to_owned());
+    if authority.is_empty()
+        || !authority
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+    {
+        return Err(format!(
+            "Invalid uProtocol authority {authority:?}, use letters, digits, '-' and '.'"
+        )
+        .into());
+    }
+
+    let ue_version = args
+        .ue_version
+        .or(file.ue_version)
+        .unwrap_or(DEFAULT_UE_VERSION);
+    if ue_version == 0 {
+        return Err("uProtocol entity major version must not be 0".into());
+    }
+
+    let zenoh_config = args.zenoh_config.or(file.zenoh_config);
+    if let Some(path) = &zenoh_config
+        && !path.is_file()
+    {
+        return Err(format!("Zenoh config file {} does not exist", path.display()).into());
+    }
+
+    let log_level = args
+        .log_level
+        .or(file.log_level)
+        .unwrap_or_else(|| DEFAULT_LOG_LEVEL.to_owned());
+    if log_level.trim().is_empty() {
+        return Err("Log level must not be empty".into());
+    }
+
+    let otlp_endpoint = args.otlp_endpoint.or(file.otlp_endpoint);
+    if let Some(endpoint) = &otlp_endpoint
+        && !(endpoint.starts_with("http://") || endpoint.starts_with("https://"))
+    {
+        return Err(
+            format!("OTLP endpoint {endpoint:?} must start with http:// or https://").into(),
+        );
+    }
+
+    let record_max_file_mb = args
+        .record_max_file_mb
+        .or(file.record_max_file_mb)
+        .unwrap_or(DEFAULT_RECORD_MAX_FILE_MB);
+    let record_max_files = args
+        .record_max_files
+        .or(file.record_max_files)
+        .unwrap_or(DEFAULT_RECORD_MAX_FILES);
+    if record_max_file_mb == 0 || record_max_files == 0 {
+        return Err("Recording file size and number of files must be greater than 0".into());
+    }
+    let record = args
+        .record_dir
+        .or(file.record_dir)
+        .map(|dir| RecorderOptions {
+            dir,
+            max_file_size: record_max_file_mb * 1024 * 1024,
+            max_files: record_max_files,
+        });
+
+    Ok(Config {
+        kuksa_uri,
+        authority,
+        ue_id: args.ue_id.or(file.ue_id).unwrap_or(DEFAULT_UE_ID),
+        ue_version,
+        zenoh: ZenohOptions {
+            config_file: zenoh_config,
+            mode: args.zenoh_mode.or(file.zenoh_mode),
+            router: args.router.or(file.router),
+            connect: non_empty_or(args.connect, file.connect),
+            listen: non_empty_or(args.listen, file.listen),
+            tls_root_ca: args.tls_root_ca.or(file.tls_root_ca),
+            tls_certificate: args.tls_certificate.or(file.tls_certificate),
+            tls_private_key: args.tls_private_key.or(file.tls_private_key),
+        },
+        mapping: args
+            .mapping
+            .or(file.mapping)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_MAPPING)),
+        log_level,
+        log_format: args.log_format.or(file.log_format).unwrap_or_default(),
+        otlp_endpoint,
+        poll: args.poll.or(file.poll).unwrap_or(false),
+        offline_policy: args
+            .offline_policy
+            .or(file.offline_policy)
+            .unwrap_or_default(),
+        record,
+        health_port: args
+            .health_port
+            .or(file.health_port)
+            .unwrap_or(DEFAULT_HEALTH_PORT),
+        health_interval_ms: args
+            .health_interval_ms
+            .or(file.health_interval_ms)
+            .unwrap_or(DEFAULT_HEALTH_INTERVAL_MS),
+    })
+}
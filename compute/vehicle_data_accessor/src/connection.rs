@@ -0,0 +1,83 @@
+// Copyright (c) 2025 Eclipse Foundation and others.
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use kuksa_rust_sdk::kuksa::common::ClientError;
+use std::time::Duration;
+
+pub(crate) use carmate_service::health::{Connection, ConnectionState};
+
+const BACKOFF_INITIAL: Duration = Duration::from_millis(500);
+const BACKOFF_MAX: Duration = Duration::from_secs(30);
+
+// Connections of the vehicle data accessor
+pub(crate) struct Connections {
+    pub kuksa: Connection,
+    pub zenoh: Connection,
+}
+
+impl Connections {
+    pub(crate) const fn new() -> Self {
+        Connections {
+            kuksa: Connection::new("Kuksa"),
+            zenoh: Connection::new("Zenoh"),
+        }
+    }
+
+    pub(crate) fn all(&self) -> [&Connection; 2] {
+        [&self.kuksa, &self.zenoh]
+    }
+}
+
+// gRPC status code of a Kuksa client error, errors raised by the client itself count as unknown
+pub(crate) fn error_code(err: &ClientError) -> tonic::Code {
+    match err {
+        ClientError::Connection(_) => tonic::Code::Unavailable,
+        ClientError::Status(status) => status.code(),
+        ClientError::Function(_) => tonic::Code::Unknown,
+    }
+}
+
+// Whether a Kuksa client error means the databroker is not reachable
+pub(crate) fn is_connection_error(err: &ClientError) -> bool {
+    error_code(err) == tonic::Code::Unavailable
+}
+
+// Exponential backoff between reconnection attempts
+pub(crate) struct Backoff {
+    delay: Duration,
+}
+
+impl Backoff {
+    pub(crate) fn new() -> Self {
+        Backoff {
+            delay: BACKOFF_INITIAL,
+        }
+    }
+
+    // Delay before the next attempt, doubling up to the maximum
+    pub(crate) fn next_delay(&mut self) -> Duration {
+        let delay = self.delay;
+        self.delay = (self.delay * 2).min(BACKOFF_MAX);
+        delay
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.delay = BACKOFF_INITIAL;
+    }
+}
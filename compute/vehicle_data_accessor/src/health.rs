@@ -0,0 +1,54 @@
+// Copyright (c) 2025 Eclipse Foundation and others.
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::connection::Connections;
+use carmate_service::health::{self as service, HealthMessage};
+use carmate_service::http::Response;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use up_rust::StaticUriProvider;
+use up_transport_zenoh::UPTransportZenoh;
+
+pub(crate) use carmate_service::health::RESOURCE_ID;
+
+pub(crate) const SERVICE: &str = "vehicle_data_accessor";
+
+fn message(connections: &Connections, started: Instant) -> HealthMessage {
+    HealthMessage::new(SERVICE, &connections.all(), started)
+}
+
+// Container probes: /healthz while the process serves requests, /readyz while all connections are up
+pub(crate) fn probe(path: &str, connections: &Connections, started: Instant) -> Response {
+    service::probe(path, &message(connections, started))
+}
+
+// Publish the health message every interval. It is sent directly, as an old
+// health message kept by the offline policy would be misleading.
+pub(crate) async fn publish_periodically(
+    transport: Arc<UPTransportZenoh>,
+    uri_provider: Arc<StaticUriProvider>,
+    connections: Arc<Connections>,
+    started: Instant,
+    interval: Duration,
+) {
+    service::publish_periodically(transport.as_ref(), uri_provider.as_ref(), interval, || {
+        message(&connections, started)
+    })
+    .await;
+}
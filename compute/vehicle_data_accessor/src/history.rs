@@ -0,0 +1,244 @@
+// Copyright (c) 2025 Eclipse Foundation and others.
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::mapping::{HistoryConfig, SignalMapping};
+use crate::policy;
+use kuksa_rust_sdk::v2_proto;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use tokio::sync::Mutex;
+
+// A recorded value with its timestamp in milliseconds since the Unix epoch
+#[derive(Debug, Clone)]
+pub(crate) struct HistorySample {
+    pub timestamp: i64,
+    pub value: v2_proto::Value,
+}
+
+// Numeric samples aggregated over one time bucket
+#[derive(Serialize, Debug)]
+pub(crate) struct Bucket {
+    // Start of the bucket in milliseconds since the Unix epoch
+    start: i64,
+    count: usize,
+    min: f64,
+    max: f64,
+    avg: f64,
+}
+
+// Ring buffer of the recent values of every mapped signal, shared with the RPC handlers
+pub(crate) struct History {
+    retention_ms: i64,
+    max_samples: usize,
+    series: Mutex<HashMap<String, VecDeque<HistorySample>>>,
+}
+
+impl History {
+    pub(crate) fn new(config: &HistoryConfig, signals: &[SignalMapping]) -> Self {
+        History {
+            retention_ms: i64::try_from(config.retention_ms).unwrap_or(i64::MAX),
+            max_samples: config.max_samples,
+            series: Mutex::new(
+                signals
+                    .iter()
+                    .map(|signal| (signal.path.clone(), VecDeque::new()))
+                    .collect(),
+            ),
+        }
+    }
+
+    // Append a value, dropping values older than the retention or beyond the sample limit
+    pub(crate) async fn record(&self, path: &str, timestamp: i64, value: v2_proto::Value) {
+        let mut series = self.series.lock().await;
+        let Some(samples) = series.get_mut(path) else {
+            return;
+        };
+        if samples
+            .back()
+            .is_some_and(|last| last.timestamp >= timestamp)
+        {
+            // The same datapoint read again by polling
+            return;
+        }
+        samples.push_back(HistorySample { timestamp, value });
+        let oldest = timestamp.saturating_sub(self.retention_ms);
+        while samples.len() > self.max_samples
+            || samples
+                .front()
+                .is_some_and(|sample| sample.timestamp < oldest)
+        {
+            samples.pop_front();
+        }
+    }
+
+    // Samples of a signal within from..=to, None if the signal is not recorded
+    pub(crate) async fn range(&self, path: &str, from: i64, to: i64) -> Option<Vec<HistorySample>> {
+        let series = self.series.lock().await;
+        let samples = series.get(path)?;
+        Some(
+            samples
+                .iter()
+                .filter(|sample| (from..=to).contains(&sample.timestamp))
+                .cloned()
+                .collect(),
+        )
+    }
+
+    pub(crate) fn retention_ms(&self) -> i64 {
+        self.retention_ms
+    }
+}
+
+// Aggregate numeric samples into buckets of bucket_ms starting at from, empty buckets are left out
+pub(crate) fn downsample(
+    samples: &[HistorySample],
+    from: i64,
+    bucket_ms: i64,
+) -> Result<Vec<Bucket>, String> {
+    let mut buckets: Vec<Bucket> = Vec::new();
+    for sample in samples {
+        let number = policy::as_number(&sample.value)
+            .ok_or_else(|| "Downsampling requires a numeric signal".to_owned())?;
+        let start = from + (sample.timestamp - from) / bucket_ms * bucket_ms;
+        match buckets.last_mut() {
+            Some(bucket) if bucket.start == start => {
+                bucket.min = bucket.min.min(number);
+                bucket.max = bucket.max.max(number);
+                // Running average, so no separate sum is needed
+                bucket.count += 1;
+                bucket.avg += (number - bucket.avg) / bucket.count as f64;
+            }
+            _ => buckets.push(Bucket {
+                start,
+                count: 1,
+                min: number,
+                max: number,
+                avg: number,
+            }),
+        }
+    }
+    Ok(buckets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(timestamp: i64, value: f64) -> HistorySample {
+        HistorySample {
+            timestamp,
+            value: v2_proto::Value {
+                typed_value: Some(v2_proto::value::TypedValue::Double(value)),
+            },
+        }
+    }
+
+    // Start, count, min, max and average of the buckets
+    fn summary(buckets: &[Bucket]) -> Vec<(i64, usize, f64, f64, f64)> {
+        buckets
+            .iter()
+            .map(|bucket| {
+                (
+                    bucket.start,
+                    bucket.count,
+                    bucket.min,
+                    bucket.max,
+                    bucket.avg,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn no_samples_give_no_buckets() {
+        assert!(downsample(&[], 0, 1000).unwrap().is_empty());
+    }
+
+    #[test]
+    fn buckets_start_at_from() {
+        let samples = [
+            sample(1500, 4.0),
+            sample(1999, 1.0),
+            sample(2499, 7.0),
+            sample(2500, 2.0),
+        ];
+        let buckets = downsample(&samples, 1500, 1000).unwrap();
+        assert_eq!(
+            summary(&buckets),
+            [(1500, 3, 1.0, 7.0, 4.0), (2500, 1, 2.0, 2.0, 2.0)]
+        );
+    }
+
+    #[test]
+    fn empty_buckets_are_left_out() {
+        let samples = [sample(0, 1.0), sample(3000, 3.0), sample(3999, 5.0)];
+        let buckets = downsample(&samples, 0, 1000).unwrap();
+        assert_eq!(
+            summary(&buckets),
+            [(0, 1, 1.0, 1.0, 1.0), (3000, 2, 3.0, 5.0, 4.0)]
+        );
+    }
+
+    #[test]
+    fn average_of_many_samples() {
+        let samples: Vec<HistorySample> = (0..10)
+            .map(|index| sample(index, index as f64 * 0.1))
+            .collect();
+        let buckets = downsample(&samples, 0, 10).unwrap();
+        assert_eq!(buckets.len(), 1);
+        assert_eq!(buckets[0].count, 10);
+        assert!((buckets[0].avg - 0.45).abs() < 1e-9);
+    }
+
+    #[test]
+    fn timestamps_before_the_epoch() {
+        let samples = [sample(-1000, 1.0), sample(-501, 2.0), sample(-500, 3.0)];
+        let buckets = downsample(&samples, -1000, 500).unwrap();
+        assert_eq!(
+            summary(&buckets),
+            [(-1000, 2, 1.0, 2.0, 1.5), (-500, 1, 3.0, 3.0, 3.0)]
+        );
+    }
+
+    #[test]
+    fn integer_signals_are_downsampled() {
+        let samples = [HistorySample {
+            timestamp: 0,
+            value: v2_proto::Value {
+                typed_value: Some(v2_proto::value::TypedValue::Uint32(7)),
+            },
+        }];
+        let buckets = downsample(&samples, 0, 1000).unwrap();
+        assert_eq!(summary(&buckets), [(0, 1, 7.0, 7.0, 7.0)]);
+    }
+
+    #[test]
+    fn non_numeric_signals_are_rejected() {
+        let samples = [
+            sample(0, 1.0),
+            HistorySample {
+                timestamp: 1,
+                value: v2_proto::Value {
+                    typed_value: Some(v2_proto::value::TypedValue::String("FF0000".to_owned())),
+                },
+            },
+        ];
+        assert!(downsample(&samples, 0, 1000).is_err());
+    }
+}
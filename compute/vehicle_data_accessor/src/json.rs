@@ -0,0 +1,377 @@
+// Copyright (c) 2025 Eclipse Foundation and others.
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::status::SignalStatus;
+use kuksa_rust_sdk::v2_proto;
+use serde::Serialize;
+use serde_json::json;
+
+pub(crate) use carmate_service::health::now_millis;
+
+// Published signal value including the VSS metadata and the status of the signal
+#[derive(Serialize, Debug)]
+pub(crate) struct SignalMessage {
+    path: String,
+    value: serde_json::Value,
+    datatype: Option<String>,
+    unit: Option<String>,
+    description: Option<String>,
+    // Source timestamp in milliseconds since the Unix epoch
+    timestamp: Option<i64>,
+    status: SignalStatus,
+}
+
+impl SignalMessage {
+    pub(crate) fn new(
+        path: &str,
+        value: Option<&v2_proto::Value>,
+        metadata: Option<&v2_proto::Metadata>,
+        timestamp: Option<i64>,
+        status: SignalStatus,
+    ) -> Self {
+        SignalMessage {
+            path: path.to_owned(),
+            value: value.map(value_to_json).unwrap_or_default(),
+            datatype: metadata.and_then(|metadata| data_type_name(metadata.data_type)),
+            unit: metadata
+                .map(|metadata| metadata.unit.clone())
+                .filter(|unit| !unit.is_empty()),
+            description: metadata
+                .map(|metadata| metadata.description.clone())
+                .filter(|description| !description.is_empty()),
+            timestamp,
+            status,
+        }
+    }
+}
+
+// VSS name of a databroker datatype, e.g. "float" or "string[]"
+pub(crate) fn data_type_name(data_type: i32) -> Option<String> {
+    let data_type = v2_proto::DataType::try_from(data_type).ok()?;
+    let name = data_type
+        .as_str_name()
+        .trim_start_matches("DATA_TYPE_")
+        .to_lowercase();
+    Some(match name.strip_suffix("_array") {
+        Some(element) => format!("{element}[]"),
+        None => name,
+    })
+}
+
+// Databroker datatype matching the representation of a value, e.g. for recording
+pub(crate) fn value_data_type(value: &v2_proto::Value) -> Option<v2_proto::DataType> {
+    Some(match value.typed_value.as_ref()? {
+        v2_proto::value::TypedValue::Bool(_) => v2_proto::DataType::Boolean,
+        v2_proto::value::TypedValue::Int32(_) => v2_proto::DataType::Int32,
+        v2_proto::value::TypedValue::Int64(_) => v2_proto::DataType::Int64,
+        v2_proto::value::TypedValue::Uint32(_) => v2_proto::DataType::Uint32,
+        v2_proto::value::TypedValue::Uint64(_) => v2_proto::DataType::Uint64,
+        v2_proto::value::TypedValue::Float(_) => v2_proto::DataType::Float,
+        v2_proto::value::TypedValue::Double(_) => v2_proto::DataType::Double,
+        v2_proto::value::TypedValue::String(_) => v2_proto::DataType::String,
+        v2_proto::value::TypedValue::StringArray(_) => v2_proto::DataType::StringArray,
+        v2_proto::value::TypedValue::BoolArray(_) => v2_proto::DataType::BooleanArray,
+        v2_proto::value::TypedValue::Int32Array(_) => v2_proto::DataType::Int32Array,
+        v2_proto::value::TypedValue::Int64Array(_) => v2_proto::DataType::Int64Array,
+        v2_proto::value::TypedValue::Uint32Array(_) => v2_proto::DataType::Uint32Array,
+        v2_proto::value::TypedValue::Uint64Array(_) => v2_proto::DataType::Uint64Array,
+        v2_proto::value::TypedValue::FloatArray(_) => v2_proto::DataType::FloatArray,
+        v2_proto::value::TypedValue::DoubleArray(_) => v2_proto::DataType::DoubleArray,
+    })
+}
+
+// Convert a databroker value into its JSON representation
+pub(crate) fn value_to_json(value: &v2_proto::Value) -> serde_json::Value {
+    match &value.typed_value {
+        Some(value) => match value {
+            v2_proto::value::TypedValue::Bool(value) => json!(value),
+            v2_proto::value::TypedValue::Int32(value) => json!(value),
+            v2_proto::value::TypedValue::Int64(value) => json!(value),
+            v2_proto::value::TypedValue::Uint32(value) => json!(value),
+            v2_proto::value::TypedValue::Uint64(value) => json!(value),
+            v2_proto::value::TypedValue::Float(value) => json!(value),
+            v2_proto::value::TypedValue::Double(value) => json!(value),
+            v2_proto::value::TypedValue::String(value) => json!(value),
+            v2_proto::value::TypedValue::StringArray(array) => json!(array.values),
+            v2_proto::value::TypedValue::BoolArray(array) => json!(array.values),
+            v2_proto::value::TypedValue::Int32Array(array) => json!(array.values),
+            v2_proto::value::TypedValue::Int64Array(array) => json!(array.values),
+            v2_proto::value::TypedValue::Uint32Array(array) => json!(array.values),
+            v2_proto::value::TypedValue::Uint64Array(array) => json!(array.values),
+            v2_proto::value::TypedValue::FloatArray(array) => json!(array.values),
+            v2_proto::value::TypedValue::DoubleArray(array) => json!(array.values),
+        },
+        None => serde_json::Value::Null,
+    }
+}
+
+// Milliseconds since the Unix epoch of a datapoint's source timestamp
+pub(crate) fn timestamp_millis(datapoint: &v2_proto::Datapoint) -> Option<i64> {
+    datapoint
+        .timestamp
+        .as_ref()
+        .map(|timestamp| timestamp.seconds * 1000 + i64::from(timestamp.nanos) / 1_000_000)
+}
+
+// Convert a JSON value into a databroker value of the given VSS datatype
+pub(crate) fn value_from_json(
+    json: &serde_json::Value,
+    data_type: v2_proto::DataType,
+) -> Result<v2_proto::Value, String> {
+    let typed_value = match data_type {
+        v2_proto::DataType::String => v2_proto::value::TypedValue::String(to_string(json)?),
+        v2_proto::DataType::Boolean => v2_proto::value::TypedValue::Bool(to_bool(json)?),
+        // Narrow types are checked against their own range and widened for the databroker
+        v2_proto::DataType::Int8 => v2_proto::value::TypedValue::Int32(to_int::<i8>(json)?.into()),
+        v2_proto::DataType::Int16 => {
+            v2_proto::value::TypedValue::Int32(to_int::<i16>(json)?.into())
+        }
+        v2_proto::DataType::Int32 => v2_proto::value::TypedValue::Int32(to_int(json)?),
+        v2_proto::DataType::Int64 => v2_proto::value::TypedValue::Int64(to_int(json)?),
+        v2_proto::DataType::Uint8 => {
+            v2_proto::value::TypedValue::Uint32(to_uint::<u8>(json)?.into())
+        }
+        v2_proto::DataType::Uint16 => {
+            v2_proto::value::TypedValue::Uint32(to_uint::<u16>(json)?.into())
+        }
+        v2_proto::DataType::Uint32 => v2_proto::value::TypedValue::Uint32(to_uint(json)?),
+        v2_proto::DataType::Uint64 => v2_proto::value::TypedValue::Uint64(to_uint(json)?),
+        v2_proto::DataType::Float => v2_proto::value::TypedValue::Float(to_float(json)? as f32),
+        v2_proto::DataType::Double => v2_proto::value::TypedValue::Double(to_float(json)?),
+        v2_proto::DataType::StringArray => {
+            v2_proto::value::TypedValue::StringArray(v2_proto::StringArray {
+                values: to_array(json, to_string)?,
+            })
+        }
+        v2_proto::DataType::BooleanArray => {
+            v2_proto::value::TypedValue::BoolArray(v2_proto::BoolArray {
+                values: to_array(json, to_bool)?,
+            })
+        }
+        v2_proto::DataType::Int8Array => {
+            v2_proto::value::TypedValue::Int32Array(v2_proto::Int32Array {
+                values: to_array(json, |json| to_int::<i8>(json).map(i32::from))?,
+            })
+        }
+        v2_proto::DataType::Int16Array => {
+            v2_proto::value::TypedValue::Int32Array(v2_proto::Int32Array {
+                values: to_array(json, |json| to_int::<i16>(json).map(i32::from))?,
+            })
+        }
+        v2_proto::DataType::Int32Array => {
+            v2_proto::value::TypedValue::Int32Array(v2_proto::Int32Array {
+                values: to_array(json, to_int)?,
+            })
+        }
+        v2_proto::DataType::Int64Array => {
+            v2_proto::value::TypedValue::Int64Array(v2_proto::Int64Array {
+                values: to_array(json, to_int)?,
+            })
+        }
+        v2_proto::DataType::Uint8Array => {
+            v2_proto::value::TypedValue::Uint32Array(v2_proto::Uint32Array {
+                values: to_array(json, |json| to_uint::<u8>(json).map(u32::from))?,
+            })
+        }
+        v2_proto::DataType::Uint16Array => {
+            v2_proto::value::TypedValue::Uint32Array(v2_proto::Uint32Array {
+                values: to_array(json, |json| to_uint::<u16>(json).map(u32::from))?,
+            })
+        }
+        v2_proto::DataType::Uint32Array => {
+            v2_proto::value::TypedValue::Uint32Array(v2_proto::Uint32Array {
+                values: to_array(json, to_uint)?,
+            })
+        }
+        v2_proto::DataType::Uint64Array => {
+            v2_proto::value::TypedValue::Uint64Array(v2_proto::Uint64Array {
+                values: to_array(json, to_uint)?,
+            })
+        }
+        v2_proto::DataType::FloatArray => {
+            v2_proto::value::TypedValue::FloatArray(v2_proto::FloatArray {
+                values: to_array(json, |json| to_float(json).map(|value| value as f32))?,
+            })
+        }
+        v2_proto::DataType::DoubleArray => {
+            v2_proto::value::TypedValue::DoubleArray(v2_proto::DoubleArray {
+                values: to_array(json, to_float)?,
+            })
+        }
+        other => return Err(format!("Unsupported VSS datatype {other:?}")),
+    };
+    Ok(v2_proto::Value {
+        typed_value: Some(typed_value),
+    })
+}
+
+fn to_string(json: &serde_json::Value) -> Result<String, String> {
+    json.as_str()
+        .map(str::to_owned)
+        .ok_or_else(|| format!("Expected a string, got {json}"))
+}
+
+fn to_bool(json: &serde_json::Value) -> Result<bool, String> {
+    json.as_bool()
+        .ok_or_else(|| format!("Expected a boolean, got {json}"))
+}
+
+fn to_int<T: TryFrom<i64>>(json: &serde_json::Value) -> Result<T, String> {
+    json.as_i64()
+        .and_then(|value| T::try_from(value).ok())
+        .ok_or_else(|| format!("Expected an integer in range, got {json}"))
+}
+
+fn to_uint<T: TryFrom<u64>>(json: &serde_json::Value) -> Result<T, String> {
+    json.as_u64()
+        .and_then(|value| T::try_from(value).ok())
+        .ok_or_else(|| format!("Expected an unsigned integer in range, got {json}"))
+}
+
+fn to_float(json: &serde_json::Value) -> Result<f64, String> {
+    json.as_f64()
+        .ok_or_else(|| format!("Expected a number, got {json}"))
+}
+
+fn to_array<T>(
+    json: &serde_json::Value,
+    convert: impl Fn(&serde_json::Value) -> Result<T, String>,
+) -> Result<Vec<T>, String> {
+    json.as_array()
+        .ok_or_else(|| format!("Expected an array, got {json}"))?
+        .iter()
+        .map(convert)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(typed_value: v2_proto::value::TypedValue) -> v2_proto::Value {
+        v2_proto::Value {
+            typed_value: Some(typed_value),
+        }
+    }
+
+    #[test]
+    fn integers_in_range() {
+        assert_eq!(
+            value_from_json(&json!(-128), v2_proto::DataType::Int8),
+            Ok(value(v2_proto::value::TypedValue::Int32(-128)))
+        );
+        assert_eq!(
+            value_from_json(&json!(255), v2_proto::DataType::Uint8),
+            Ok(value(v2_proto::value::TypedValue::Uint32(255)))
+        );
+        assert_eq!(
+            value_from_json(&json!(i64::MIN), v2_proto::DataType::Int64),
+            Ok(value(v2_proto::value::TypedValue::Int64(i64::MIN)))
+        );
+        assert_eq!(
+            value_from_json(&json!(u64::MAX), v2_proto::DataType::Uint64),
+            Ok(value(v2_proto::value::TypedValue::Uint64(u64::MAX)))
+        );
+    }
+
+    #[test]
+    fn rejects_integers_out_of_range() {
+        let cases = [
+            (json!(128), v2_proto::DataType::Int8),
+            (json!(-129), v2_proto::DataType::Int8),
+            (json!(40000), v2_proto::DataType::Int16),
+            (json!(i64::from(i32::MAX) + 1), v2_proto::DataType::Int32),
+            (json!(u64::MAX), v2_proto::DataType::Int64),
+            (json!(256), v2_proto::DataType::Uint8),
+            (json!(70000), v2_proto::DataType::Uint16),
+            (json!(u64::from(u32::MAX) + 1), v2_proto::DataType::Uint32),
+        ];
+        for (json, data_type) in cases {
+            assert!(
+                value_from_json(&json, data_type).is_err(),
+                "{json} accepted as {data_type:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_negative_unsigned() {
+        for data_type in [
+            v2_proto::DataType::Uint8,
+            v2_proto::DataType::Uint16,
+            v2_proto::DataType::Uint32,
+            v2_proto::DataType::Uint64,
+        ] {
+            assert_eq!(
+                value_from_json(&json!(-1), data_type),
+                Err("Expected an unsigned integer in range, got -1".to_owned())
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_floats_for_integers() {
+        for data_type in [
+            v2_proto::DataType::Int8,
+            v2_proto::DataType::Int32,
+            v2_proto::DataType::Int64,
+            v2_proto::DataType::Uint32,
+            v2_proto::DataType::Uint64,
+        ] {
+            assert!(value_from_json(&json!(1.5), data_type).is_err());
+            assert!(value_from_json(&json!(1.0), data_type).is_err());
+        }
+        assert_eq!(
+            value_from_json(&json!(1), v2_proto::DataType::Float),
+            Ok(value(v2_proto::value::TypedValue::Float(1.0)))
+        );
+    }
+
+    #[test]
+    fn array_elements() {
+        assert_eq!(
+            value_from_json(&json!([1, -2]), v2_proto::DataType::Int8Array),
+            Ok(value(v2_proto::value::TypedValue::Int32Array(
+                v2_proto::Int32Array {
+                    values: vec![1, -2],
+                }
+            )))
+        );
+        assert_eq!(
+            value_from_json(&json!([1, "2"]), v2_proto::DataType::Int32Array),
+            Err("Expected an integer in range, got \"2\"".to_owned())
+        );
+        assert_eq!(
+            value_from_json(&json!([1, 300]), v2_proto::DataType::Uint8Array),
+            Err("Expected an unsigned integer in range, got 300".to_owned())
+        );
+        assert_eq!(
+            value_from_json(&json!([true, 1]), v2_proto::DataType::BooleanArray),
+            Err("Expected a boolean, got 1".to_owned())
+        );
+        assert_eq!(
+            value_from_json(&json!(["a", null]), v2_proto::DataType::StringArray),
+            Err("Expected a string, got null".to_owned())
+        );
+        assert_eq!(
+            value_from_json(&json!("a"), v2_proto::DataType::StringArray),
+            Err("Expected an array, got \"a\"".to_owned())
+        );
+    }
+}
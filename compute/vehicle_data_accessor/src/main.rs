@@ -17,38 +17,50 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
-use clap::Parser;
+mod config;
+mod connection;
+mod health;
+mod history;
+mod json;
+mod mapping;
+mod metadata;
+mod metrics;
+mod policy;
+mod publisher;
+mod recorder;
+mod rpc;
+mod snapshot;
+mod status;
+
+use carmate_service::{http, shutdown, telemetry};
+use connection::{Backoff, ConnectionState, Connections};
+use history::History;
 use kuksa_rust_sdk::kuksa::common::ClientTraitV2;
 use kuksa_rust_sdk::kuksa::val::v2::KuksaClientV2;
 use kuksa_rust_sdk::v2_proto;
+use mapping::SignalMapping;
+use metadata::MetadataCache;
+use metrics::Metrics;
+use publisher::Publisher;
+use recorder::Recorder;
+use std::collections::HashMap;
 use std::fmt;
-use std::time::Duration;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tracing::{info, warn};
 use up_rust::LocalUriProvider;
 use up_rust::StaticUriProvider;
-use up_rust::UMessageBuilder;
-use up_rust::UPayloadFormat;
-use up_rust::UTransport;
+use up_rust::communication::{InMemoryRpcServer, RequestHandler, RpcServer};
 use up_transport_zenoh::UPTransportZenoh;
-use up_transport_zenoh::zenoh_config;
-use zenoh::Config;
-
-#[derive(Parser, Debug)]
-#[clap(author, version, about, long_about = None)]
-struct Args {
-    #[clap(long, default_value = "127.0.0.1")]
-    host: String,
-    #[clap(long, default_value = None)]
-    router: Option<String>,
-}
 
-const TOPIC_TEMP: u16 = 0x8001;
-const TOPIC_WETNESS: u16 = 0x8002;
-const TOPIC_SPEED: u16 = 0x8003;
-const TOPIC_POS_LAT: u16 = 0x8004;
-const TOPIC_POS_LON: u16 = 0x8005;
-const TOPIC_POS_ALT: u16 = 0x8006;
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+// Time to send messages still pending on shutdown
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(2);
+// Interval for checking held back changes and heartbeats
+const TICK_INTERVAL: Duration = Duration::from_millis(100);
 
-struct DisplayDatapoint(v2_proto::Value);
+pub(crate) struct DisplayDatapoint(v2_proto::Value);
 
 fn display_array<T>(f: &mut fmt::Formatter<'_>, array: &[T]) -> fmt::Result
 where
@@ -91,24 +103,113 @@ impl fmt::Display for DisplayDatapoint {
     }
 }
 
-// Helper function to create a Zenoh configuration
-pub(crate) fn get_zenoh_config() -> zenoh_config::Config {
-    let args = Args::parse();
+// Poll all mapped signals periodically, for databrokers without subscription support.
+// All signals are read with one GetValues request, giving a consistent snapshot per cycle.
+async fn poll_signals(
+    client: &mut KuksaClientV2,
+    signals: &[SignalMapping],
+    publisher: &mut Publisher,
+    connections: &Connections,
+    metrics: &Metrics,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let paths: Vec<String> = signals.iter().map(|signal| signal.path.clone()).collect();
+    let mut backoff = Backoff::new();
+    loop {
+        let reachable = match client.get_values(paths.clone()).await {
+            Ok(datapoints) => {
+                connections.kuksa.succeeded();
+                // The databroker answers in the order of the requested paths
+                for (signal, datapoint) in signals.iter().zip(datapoints) {
+                    publisher.handle(signal, datapoint).await?;
+                }
+                true
+            }
+            Err(err) if connection::is_connection_error(&err) => {
+                warn!(?err, "Kuksa databroker not reachable");
+                connections.kuksa.failed();
+                metrics.grpc_error(connection::error_code(&err));
+                false
+            }
+            Err(err) => {
+                warn!(?paths, ?err, "Getting values failed");
+                connections.kuksa.error();
+                metrics.grpc_error(connection::error_code(&err));
+                true
+            }
+        };
+
+        publisher.tick(signals).await?;
 
-    let zenoh_string = if let Some(router) = &args.router {
-        format!(
-            "{{ mode: 'peer', connect: {{ endpoints: [ 'tcp/{}:7447' ] }} }}",
-            router
-        )
-    } else {
-        "{ mode: 'peer' }".to_string()
-    };
+        if reachable {
+            connections.kuksa.set(ConnectionState::Connected);
+            backoff.reset();
+            tokio::time::sleep(POLL_INTERVAL).await;
+        } else {
+            connections.kuksa.set(ConnectionState::Disconnected);
+            publisher.broker_error(signals).await?;
+            tokio::time::sleep(backoff.next_delay()).await;
+        }
+    }
+}
 
-    println!("Zenoh String: {:?}", zenoh_string);
+// Subscribe to all mapped signals and publish each datapoint as it arrives
+async fn subscribe_signals(
+    client: &mut KuksaClientV2,
+    signals: &[SignalMapping],
+    publisher: &mut Publisher,
+    connections: &Connections,
+    metrics: &Metrics,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let by_path: HashMap<&str, &SignalMapping> = signals
+        .iter()
+        .map(|signal| (signal.path.as_str(), signal))
+        .collect();
+    let paths: Vec<String> = signals.iter().map(|signal| signal.path.clone()).collect();
+    let mut backoff = Backoff::new();
 
-    let zenoh_config = Config::from_json5(&zenoh_string).expect("Failed to load Zenoh config");
+    loop {
+        match client.subscribe(paths.clone(), None).await {
+            Ok(mut stream) => {
+                info!(signals = paths.len(), "Subscribed");
+                connections.kuksa.set(ConnectionState::Connected);
+                backoff.reset();
+                let mut ticker = tokio::time::interval(TICK_INTERVAL);
+                loop {
+                    tokio::select! {
+                        message = stream.message() => match message {
+                            Ok(Some(response)) => {
+                                connections.kuksa.succeeded();
+                                for (path, datapoint) in response.entries {
+                                    match by_path.get(path.as_str()) {
+                                        Some(signal) => publisher.handle(signal, datapoint).await?,
+                                        None => warn!(%path, "Got update for unmapped signal"),
+                                    }
+                                }
+                            }
+                            Ok(None) => {
+                                warn!("Subscription closed by databroker");
+                                break;
+                            }
+                            Err(status) => {
+                                warn!(?status, "Subscription failed");
+                                metrics.grpc_error(status.code());
+                                break;
+                            }
+                        },
+                        _ = ticker.tick() => publisher.tick(signals).await?,
+                    }
+                }
+            }
+            Err(err) => {
+                warn!(?paths, ?err, "Subscribing failed");
+                metrics.grpc_error(connection::error_code(&err));
+            }
+        }
 
-    zenoh_config
+        connections.kuksa.failed();
+        publisher.broker_error(signals).await?;
+        tokio::time::sleep(backoff.next_delay()).await;
+    }
 }
 
 #[tokio::main]
@@ -117,274 +218,181 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // - Started after MQTT Broker and Kuksa Data broker
     // --> This is achieved with ankaios configuration
 
-    // Initialize Kuksa Client
-    let host = "http://localhost:55555";
-    let mut v2_client: KuksaClientV2 = KuksaClientV2::from_host(host);
+    let started = Instant::now();
+    let config = config::load()?;
+    // Logging of the accessor and of uProtocol and Zenoh, RUST_LOG takes precedence over the configured level
+    let telemetry = telemetry::init(
+        health::SERVICE,
+        &config.log_level,
+        config.log_format,
+        config.otlp_endpoint.as_deref(),
+    )?;
+    info!(?config, "Configuration");
 
-    // Initialze uProtocol + Zenoh
+    // Load the VSS to uProtocol topic mapping
+    let mapping = mapping::load(&config.mapping)?;
+    let signals = &mapping.signals;
+    info!(
+        signals = signals.len(),
+        actuators = mapping.actuators.len(),
+        file = %config.mapping.display(),
+        "Loaded signal mapping"
+    );
 
-    // Initialize uProtocol logging
-    UPTransportZenoh::try_init_log_from_env();
+    // Initialize Kuksa Clients
+    // The RPC services share their own client, so they are not blocked by the subscription
+    let mut v2_client: KuksaClientV2 = KuksaClientV2::new(config.kuksa_uri.clone());
+    let rpc_client = Arc::new(Mutex::new(KuksaClientV2::new(config.kuksa_uri.clone())));
+    let metadata = Arc::new(MetadataCache::new(rpc_client.clone()));
+    let history = Arc::new(History::new(&mapping.history, signals));
+
+    // Initialze uProtocol + Zenoh
 
     // Create a uProtocol URI provider for this vehicle
     // This defines the identity of this node in the uProtocol network
-    let uri_provider = StaticUriProvider::new("vehicledataaccessor", 0, 2);
+    let uri_provider = Arc::new(StaticUriProvider::new(
+        &config.authority,
+        config.ue_id,
+        config.ue_version,
+    ));
     let authority = uri_provider.get_authority();
 
-    println!("uProtocol Authority: {:?}", authority);
+    info!(%authority, "uProtocol authority");
 
     // Create the uProtocol transport using Zenoh as the underlying transport
-    let transport = UPTransportZenoh::builder(authority)
-        .expect("invalid authority name")
-        .with_config(get_zenoh_config())
-        .build()
-        .await?;
-
-    loop {
-        // let test_payload = format!("{}", 25.5f32);
-        // let test_topic = uri_provider.get_resource_uri(TOPIC_TEMP);
-        // let test_message = UMessageBuilder::publish(test_topic.clone())
-        //     .build_with_payload(test_payload.clone(), UPayloadFormat::UPAYLOAD_FORMAT_TEXT)?;
-        // let _ = transport.send(test_message).await;
+    // Retry with backoff, the Zenoh router may not be up yet
+    let connections = Arc::new(Connections::new());
+    let metrics = Arc::new(Metrics::default());
 
-        let result = v2_client
-            .get_value("Vehicle.Cabin.HVAC.AmbientAirTemperature".to_owned())
-            .await;
-        match result {
-            Ok(option) => match option {
-                Some(datapoint) => {
-                    println!(
-                        "Vehicle.Cabin.HVAC.AmbientAirTemperature: {:?}",
-                        datapoint.value
-                    );
-                    match datapoint.value {
-                        Some(value) => {
-                            let printable = DisplayDatapoint(value);
-                            println!(
-                                "Got value for Vehicle.Cabin.HVAC.AmbientAirTemperature: {:?}",
-                                printable.to_string()
-                            );
-                            // Publish on uProtocol
-                            let topic = uri_provider.get_resource_uri(TOPIC_TEMP);
-                            let message = UMessageBuilder::publish(topic.clone())
-                                .build_with_payload(
-                                    printable.to_string(),
-                                    UPayloadFormat::UPAYLOAD_FORMAT_TEXT,
-                                )?;
-                            transport.send(message).await?;
-                        }
-                        None => {
-                            // TODO
-                        }
-                    }
-                }
-                None => {
-                    println!("Vehicle.Cabin.HVAC.AmbientAirTemperature not set");
-                }
-            },
-            Err(err) => {
-                println!(
-                    "Getting value for signal {:?} failed: {:?}",
-                    "Vehicle.Cabin.HVAC.AmbientAirTemperature", err
-                );
+    // Container probes and metrics, served from the start so /readyz reports the connection setup
+    if config.health_port != 0 {
+        let port = config.health_port;
+        let connections = connections.clone();
+        let metrics = metrics.clone();
+        tokio::spawn(async move {
+            let handler = move |path: &str| match path {
+                "/metrics" => metrics.response(&connections),
+                _ => health::probe(path, &connections, started),
+            };
+            if let Err(err) = http::serve(port, handler).await {
+                warn!(port, ?err, "HTTP server failed");
             }
-        }
+        });
+    }
 
-        let result = v2_client
-            .get_value("Vehicle.Exterior.Humidity".to_owned())
-            .await;
-        match result {
-            Ok(option) => match option {
-                Some(datapoint) => {
-                    println!("Vehicle.Exterior.Humidity: {:?}", datapoint.value);
-                    match datapoint.value {
-                        Some(value) => {
-                            let printable = DisplayDatapoint(value);
-                            println!(
-                                "Got value for Vehicle.Exterior.Humidity: {:?}",
-                                printable.to_string()
-                            );
-                            // Publish on uProtocol
-                            let topic = uri_provider.get_resource_uri(TOPIC_WETNESS);
-                            let message = UMessageBuilder::publish(topic.clone())
-                                .build_with_payload(
-                                    printable.to_string(),
-                                    UPayloadFormat::UPAYLOAD_FORMAT_TEXT,
-                                )?;
-                            transport.send(message).await?;
-                        }
-                        None => {
-                            // TODO
-                        }
-                    }
-                }
-                None => {
-                    println!("Vehicle.Exterior.Humidity not set");
-                }
-            },
-            Err(err) => {
-                println!(
-                    "Getting value for signal {:?} failed: {:?}",
-                    "Vehicle.Exterior.Humidity", err
-                );
+    let zenoh_config = config.zenoh.build()?;
+    let mut backoff = Backoff::new();
+    let transport = loop {
+        match UPTransportZenoh::builder(authority.clone())
+            .expect("invalid authority name")
+            .with_config(zenoh_config.clone())
+            .build()
+            .await
+        {
+            Ok(transport) => break Arc::new(transport),
+            Err(status) => {
+                warn!(?status, "Creating Zenoh transport failed");
+                connections.zenoh.set(ConnectionState::Disconnected);
+                tokio::time::sleep(backoff.next_delay()).await;
             }
         }
+    };
+    connections.zenoh.set(ConnectionState::Connected);
 
-        let result = v2_client.get_value("Vehicle.Speed".to_owned()).await;
-        match result {
-            Ok(option) => match option {
-                Some(datapoint) => {
-                    println!("Vehicle.Speed: {:?}", datapoint.value);
-                    match datapoint.value {
-                        Some(value) => {
-                            let printable = DisplayDatapoint(value);
-                            println!("Got value for Vehicle.Speed: {:?}", printable.to_string());
-                            // Publish on uProtocol
-                            let topic = uri_provider.get_resource_uri(TOPIC_SPEED);
-                            let message = UMessageBuilder::publish(topic.clone())
-                                .build_with_payload(
-                                    printable.to_string(),
-                                    UPayloadFormat::UPAYLOAD_FORMAT_TEXT,
-                                )?;
-                            transport.send(message).await?;
-                        }
-                        None => {
-                            // TODO
-                        }
-                    }
-                }
-                None => {
-                    println!("Vehicle.Speed not set");
-                }
-            },
-            Err(err) => {
-                println!(
-                    "Getting value for signal {:?} failed: {:?}",
-                    "Vehicle.Speed", err
-                );
-            }
-        }
+    // Register the RPC methods of the vehicledataaccessor entity
+    let rpc_server = InMemoryRpcServer::new(transport.clone(), uri_provider.clone());
+    let endpoints: Vec<(u16, &str, Arc<dyn RequestHandler>)> = vec![
+        (
+            rpc::METHOD_GET_SIGNAL,
+            "GetSignal",
+            Arc::new(rpc::GetSignalHandler::new(
+                rpc_client.clone(),
+                metadata.clone(),
+            )),
+        ),
+        (
+            rpc::METHOD_SET_ACTUATOR,
+            "SetActuator",
+            Arc::new(rpc::SetActuatorHandler::new(
+                rpc_client.clone(),
+                metadata.clone(),
+                &mapping.actuators,
+            )),
+        ),
+        (
+            rpc::METHOD_GET_HISTORY,
+            "GetHistory",
+            Arc::new(rpc::GetHistoryHandler::new(
+                history.clone(),
+                metadata.clone(),
+            )),
+        ),
+    ];
+    for (resource_id, name, handler) in &endpoints {
+        rpc_server
+            .register_endpoint(None, *resource_id, handler.clone())
+            .await
+            .map_err(|err| format!("Registering {name} failed: {err:?}"))?;
+    }
 
-        let result = v2_client
-            .get_value("Vehicle.CurrentLocation.Latitude".to_owned())
-            .await;
-        match result {
-            Ok(option) => match option {
-                Some(datapoint) => {
-                    println!("Vehicle.CurrentLocation.Latitude: {:?}", datapoint.value);
-                    match datapoint.value {
-                        Some(value) => {
-                            let printable = DisplayDatapoint(value);
-                            println!(
-                                "Got value for Vehicle.CurrentLocation.Latitude: {:?}",
-                                printable.to_string()
-                            );
-                            // Publish on uProtocol
-                            let topic = uri_provider.get_resource_uri(TOPIC_POS_LAT);
-                            let message = UMessageBuilder::publish(topic.clone())
-                                .build_with_payload(
-                                    printable.to_string(),
-                                    UPayloadFormat::UPAYLOAD_FORMAT_TEXT,
-                                )?;
-                            transport.send(message).await?;
-                        }
-                        None => {
-                            // TODO
-                        }
-                    }
-                }
-                None => {
-                    println!("Vehicle.CurrentLocation.Latitude not set");
-                }
-            },
-            Err(err) => {
-                println!(
-                    "Getting value for signal {:?} failed: {:?}",
-                    "Vehicle.CurrentLocation.Latitude", err
-                );
-            }
-        }
+    let health_task = (config.health_interval_ms != 0).then(|| {
+        tokio::spawn(health::publish_periodically(
+            transport.clone(),
+            uri_provider.clone(),
+            connections.clone(),
+            started,
+            Duration::from_millis(config.health_interval_ms),
+        ))
+    });
 
-        let result = v2_client
-            .get_value("Vehicle.CurrentLocation.Longitude".to_owned())
-            .await;
-        match result {
-            Ok(option) => match option {
-                Some(datapoint) => {
-                    println!("Vehicle.CurrentLocation.Longitude: {:?}", datapoint.value);
-                    match datapoint.value {
-                        Some(value) => {
-                            let printable = DisplayDatapoint(value);
-                            println!(
-                                "Got value for Vehicle.CurrentLocation.Longitude: {:?}",
-                                printable.to_string()
-                            );
-                            // Publish on uProtocol
-                            let topic = uri_provider.get_resource_uri(TOPIC_POS_LON);
-                            let message = UMessageBuilder::publish(topic.clone())
-                                .build_with_payload(
-                                    printable.to_string(),
-                                    UPayloadFormat::UPAYLOAD_FORMAT_TEXT,
-                                )?;
-                            transport.send(message).await?;
-                        }
-                        None => {
-                            // TODO
-                        }
-                    }
-                }
-                None => {
-                    println!("Vehicle.CurrentLocation.Longitude not set");
-                }
-            },
-            Err(err) => {
-                println!(
-                    "Getting value for signal {:?} failed: {:?}",
-                    "Vehicle.CurrentLocation.Longitude", err
-                );
-            }
-        }
+    let mut publisher = Publisher::new(
+        transport,
+        uri_provider,
+        metadata,
+        connections.clone(),
+        config.offline_policy,
+        &mapping,
+        history,
+    )
+    .with_metrics(metrics.clone());
+    if let Some(options) = config.record.clone() {
+        let dir = options.dir.clone();
+        let recorder = Recorder::new(options)
+            .await
+            .map_err(|err| format!("Cannot record to {}: {err}", dir.display()))?;
+        publisher = publisher.with_recorder(recorder);
+    }
 
-        let result = v2_client
-            .get_value("Vehicle.CurrentLocation.Altitude".to_owned())
-            .await;
-        match result {
-            Ok(option) => match option {
-                Some(datapoint) => {
-                    println!("Vehicle.CurrentLocation.Altitude: {:?}", datapoint.value);
-                    match datapoint.value {
-                        Some(value) => {
-                            let printable = DisplayDatapoint(value);
-                            println!(
-                                "Got value for Vehicle.CurrentLocation.Altitude: {:?}",
-                                printable.to_string()
-                            );
-                            // Publish on uProtocol
-                            let topic = uri_provider.get_resource_uri(TOPIC_POS_ALT);
-                            let message = UMessageBuilder::publish(topic.clone())
-                                .build_with_payload(
-                                    printable.to_string(),
-                                    UPayloadFormat::UPAYLOAD_FORMAT_TEXT,
-                                )?;
-                            transport.send(message).await?;
-                        }
-                        None => {
-                            // TODO
-                        }
-                    }
-                }
-                None => {
-                    println!("Vehicle.CurrentLocation.Altitude not set");
-                }
-            },
-            Err(err) => {
-                println!(
-                    "Getting value for signal {:?} failed: {:?}",
-                    "Vehicle.CurrentLocation.Altitude", err
-                );
+    let result = tokio::select! {
+        result = async {
+            if config.poll {
+                info!(signals = signals.len(), "Polling");
+                poll_signals(&mut v2_client, signals, &mut publisher, &connections, &metrics).await
+            } else {
+                subscribe_signals(&mut v2_client, signals, &mut publisher, &connections, &metrics)
+                    .await
             }
-        }
+        } => result,
+        result = shutdown::requested() => result.map_err(Into::into),
+    };
 
-        tokio::time::sleep(Duration::from_millis(500)).await;
+    // Graceful shutdown: send what is still pending, stop serving RPCs and
+    // close the Zenoh session by dropping the last reference to the transport
+    publisher.drain(DRAIN_TIMEOUT).await;
+    if let Some(health_task) = health_task {
+        health_task.abort();
+    }
+    for (resource_id, name, handler) in endpoints {
+        if let Err(err) = rpc_server
+            .unregister_endpoint(None, resource_id, handler)
+            .await
+        {
+            warn!(method = name, ?err, "Unregistering failed");
+        }
     }
+    drop(rpc_server);
+    drop(publisher);
+    info!("Shutdown complete");
+    telemetry.shutdown();
+    result
 }
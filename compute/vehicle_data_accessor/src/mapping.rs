@@ -0,0 +1,311 @@
+// Copyright (c) 2025 Eclipse Foundation and others.
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::health;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::path::Path;
+
+// uProtocol reserves resource ids 0x8000..=0xFFFE for published topics
+const TOPIC_ID_MIN: u16 = 0x8000;
+const TOPIC_ID_MAX: u16 = 0xFFFE;
+
+// Signal history kept in memory unless configured otherwise
+const DEFAULT_HISTORY_RETENTION_MS: u64 = 10 * 60 * 1000;
+const DEFAULT_HISTORY_MAX_SAMPLES: usize = 10_000;
+
+// Encoding of the uProtocol payload for a signal
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum PayloadFormat {
+    // Human readable value, as produced by DisplayDatapoint
+    #[default]
+    Text,
+    // kuksa.val.v2.Value wrapped in google.protobuf.Any
+    Protobuf,
+    // Self-describing JSON object with value, VSS metadata and timestamp
+    Json,
+}
+
+// When a value read from the databroker is forwarded to uProtocol
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum PublishPolicy {
+    // Publish every value that is read
+    #[default]
+    Always,
+    // Publish only if the value differs from the last published one
+    OnChange,
+}
+
+// One VSS signal and the uProtocol topic it is published on
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct SignalMapping {
+    pub path: String,
+    pub resource_id: u16,
+    #[serde(default)]
+    pub format: PayloadFormat,
+    #[serde(default)]
+    pub publish: PublishPolicy,
+    // On-change only: minimum absolute change, e.g. 0.5 for 0.5 °C
+    pub deadband: Option<f64>,
+    // On-change only: minimum change relative to the last published value, e.g. 0.05 for 5 %
+    pub deadband_relative: Option<f64>,
+    // Minimum time between two publishes, later changes are held back until it passed
+    pub min_interval_ms: Option<u64>,
+    // Maximum time without publish, the last value is repeated afterwards
+    pub heartbeat_ms: Option<u64>,
+    // Age after which the last value is reported as stale instead of being published
+    pub stale_after_ms: Option<u64>,
+}
+
+// A VSS actuator that may be set via RPC, with optional limits for its target value
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ActuatorRule {
+    pub path: String,
+    // Inclusive range for numeric values
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    // Accepted values for string values
+    pub allowed: Option<Vec<String>>,
+}
+
+// Aggregated topic with the latest value of all mapped signals
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct SnapshotConfig {
+    pub resource_id: u16,
+    pub interval_ms: u64,
+}
+
+// Topic on which status changes of all mapped signals are published
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct StatusConfig {
+    pub resource_id: u16,
+}
+
+// Retention of the in-memory signal history, per signal
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields, default)]
+pub(crate) struct HistoryConfig {
+    pub retention_ms: u64,
+    pub max_samples: usize,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        HistoryConfig {
+            retention_ms: DEFAULT_HISTORY_RETENTION_MS,
+            max_samples: DEFAULT_HISTORY_MAX_SAMPLES,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Mapping {
+    #[serde(rename = "signal", default)]
+    pub signals: Vec<SignalMapping>,
+    // Allow-list of writable actuators
+    #[serde(rename = "actuator", default)]
+    pub actuators: Vec<ActuatorRule>,
+    pub snapshot: Option<SnapshotConfig>,
+    pub status: Option<StatusConfig>,
+    #[serde(default)]
+    pub history: HistoryConfig,
+}
+
+// Load and validate the signal mapping file
+pub(crate) fn load(path: &Path) -> Result<Mapping, Box<dyn std::error::Error>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|err| format!("Cannot read mapping file {}: {err}", path.display()))?;
+    let file: Mapping = toml::from_str(&content)
+        .map_err(|err| format!("Cannot parse mapping file {}: {err}", path.display()))?;
+
+    if file.signals.is_empty() {
+        return Err(format!("Mapping file {} contains no signals", path.display()).into());
+    }
+    validate(&file)?;
+    Ok(file)
+}
+
+// Reject signals, topics and limits that cannot be published as configured
+fn validate(file: &Mapping) -> Result<(), Box<dyn std::error::Error>> {
+    let mut paths = HashSet::new();
+    let mut resource_ids = HashSet::new();
+    for signal in &file.signals {
+        if signal.path.is_empty() {
+            return Err("Signal mapping with empty VSS path".into());
+        }
+        if !(TOPIC_ID_MIN..=TOPIC_ID_MAX).contains(&signal.resource_id) {
+            return Err(format!(
+                "Resource id {:#06x} of {} is not a topic id ({:#06x}..={:#06x})",
+                signal.resource_id, signal.path, TOPIC_ID_MIN, TOPIC_ID_MAX
+            )
+            .into());
+        }
+        if signal.resource_id == health::RESOURCE_ID {
+            return Err(format!(
+                "Resource id {:#06x} of {} is reserved for the health topic",
+                signal.resource_id, signal.path
+            )
+            .into());
+        }
+        if signal.publish == PublishPolicy::Always
+            && (signal.deadband.is_some() || signal.deadband_relative.is_some())
+        {
+            return Err(format!(
+                "Deadband of {} requires publish = \"on-change\"",
+                signal.path
+            )
+            .into());
+        }
+        if signal.deadband.is_some_and(|deadband| deadband < 0.0)
+            || signal
+                .deadband_relative
+                .is_some_and(|deadband| deadband < 0.0)
+        {
+            return Err(format!("Deadband of {} must not be negative", signal.path).into());
+        }
+        // A zero heartbeat would republish the signal on every tick
+        if signal.min_interval_ms == Some(0) || signal.heartbeat_ms == Some(0) {
+            return Err(format!(
+                "Minimum interval and heartbeat of {} must be greater than 0",
+                signal.path
+            )
+            .into());
+        }
+        if let (Some(min_interval), Some(heartbeat)) = (signal.min_interval_ms, signal.heartbeat_ms)
+            && heartbeat < min_interval
+        {
+            return Err(format!(
+                "Heartbeat of {} must not be shorter than its minimum interval",
+                signal.path
+            )
+            .into());
+        }
+        if signal.stale_after_ms == Some(0) {
+            return Err(format!("Stale age of {} must be greater than 0", signal.path).into());
+        }
+        if !paths.insert(signal.path.as_str()) {
+            return Err(format!("VSS path {} is mapped more than once", signal.path).into());
+        }
+        if !resource_ids.insert(signal.resource_id) {
+            return Err(format!(
+                "Resource id {:#06x} is used by more than one signal",
+                signal.resource_id
+            )
+            .into());
+        }
+    }
+
+    resource_ids.insert(health::RESOURCE_ID);
+    if let Some(snapshot) = &file.snapshot {
+        if !(TOPIC_ID_MIN..=TOPIC_ID_MAX).contains(&snapshot.resource_id)
+            || resource_ids.contains(&snapshot.resource_id)
+        {
+            return Err(format!(
+                "Snapshot resource id {:#06x} is not a free topic id",
+                snapshot.resource_id
+            )
+            .into());
+        }
+        if snapshot.interval_ms == 0 {
+            return Err("Snapshot interval must be greater than 0".into());
+        }
+        resource_ids.insert(snapshot.resource_id);
+    }
+
+    if let Some(status) = &file.status
+        && (!(TOPIC_ID_MIN..=TOPIC_ID_MAX).contains(&status.resource_id)
+            || resource_ids.contains(&status.resource_id))
+    {
+        return Err(format!(
+            "Status resource id {:#06x} is not a free topic id",
+            status.resource_id
+        )
+        .into());
+    }
+
+    if file.history.retention_ms == 0 || file.history.max_samples == 0 {
+        return Err("History retention and sample limit must be greater than 0".into());
+    }
+
+    let mut actuator_paths = HashSet::new();
+    for actuator in &file.actuators {
+        if !actuator_paths.insert(actuator.path.as_str()) {
+            return Err(format!("Actuator {} is listed more than once", actuator.path).into());
+        }
+        if let (Some(min), Some(max)) = (actuator.min, actuator.max)
+            && min > max
+        {
+            return Err(format!("Actuator {} has min {min} > max {max}", actuator.path).into());
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validated(signal: &str) -> Result<(), String> {
+        let file: Mapping = toml::from_str(&format!(
+            "[[signal]]\npath = \"Vehicle.Speed\"\nresource_id = 0x8001\n{signal}"
+        ))
+        .unwrap();
+        validate(&file).map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn publish_intervals() {
+        assert_eq!(validated(""), Ok(()));
+        assert_eq!(
+            validated("min_interval_ms = 100\nheartbeat_ms = 1000"),
+            Ok(())
+        );
+        assert_eq!(
+            validated("heartbeat_ms = 0"),
+            Err(
+                "Minimum interval and heartbeat of Vehicle.Speed must be greater than 0".to_owned()
+            )
+        );
+        assert_eq!(
+            validated("min_interval_ms = 0"),
+            Err(
+                "Minimum interval and heartbeat of Vehicle.Speed must be greater than 0".to_owned()
+            )
+        );
+        assert_eq!(
+            validated("min_interval_ms = 1000\nheartbeat_ms = 100"),
+            Err(
+                "Heartbeat of Vehicle.Speed must not be shorter than its minimum interval"
+                    .to_owned()
+            )
+        );
+        assert_eq!(
+            validated("stale_after_ms = 0"),
+            Err("Stale age of Vehicle.Speed must be greater than 0".to_owned())
+        );
+    }
+}
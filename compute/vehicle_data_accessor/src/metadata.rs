@@ -0,0 +1,148 @@
+// Copyright (c) 2025 Eclipse Foundation and others.
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::connection;
+use kuksa_rust_sdk::kuksa::common::{ClientError, ClientTraitV2};
+use kuksa_rust_sdk::kuksa::val::v2::KuksaClientV2;
+use kuksa_rust_sdk::v2_proto;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tracing::warn;
+
+// Time until a path whose metadata could not be fetched is asked for again, so an
+// unreachable databroker is not queried for every signal on every publish
+const RETRY_DELAY: Duration = Duration::from_secs(30);
+
+enum Entry {
+    Found(v2_proto::Metadata),
+    // The lookup failed, its error is returned until the retry time
+    Failed {
+        code: tonic::Code,
+        message: String,
+        retry_at: Instant,
+    },
+}
+
+// VSS metadata (datatype, unit, description, ...) fetched once per path from the databroker
+pub(crate) struct MetadataCache {
+    client: Arc<Mutex<KuksaClientV2>>,
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+impl MetadataCache {
+    pub(crate) fn new(client: Arc<Mutex<KuksaClientV2>>) -> Self {
+        MetadataCache {
+            client,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Get the metadata of a VSS path, asking the databroker on the first request only
+    // and after a failed request once the retry delay passed
+    pub(crate) async fn get(&self, path: &str) -> Result<v2_proto::Metadata, ClientError> {
+        if let Some(result) = cached(&*self.entries.lock().await, path) {
+            return result;
+        }
+        self.lookup(path).await
+    }
+
+    // Get the metadata of several VSS paths, e.g. for one snapshot, with a single look into
+    // the cache. The client is only locked for paths that are not cached, paths without
+    // metadata are left out.
+    pub(crate) async fn get_all<'a>(
+        &self,
+        paths: impl IntoIterator<Item = &'a str>,
+    ) -> HashMap<&'a str, v2_proto::Metadata> {
+        let mut found = HashMap::new();
+        let mut missing = Vec::new();
+        {
+            let entries = self.entries.lock().await;
+            for path in paths {
+                match cached(&entries, path) {
+                    Some(Ok(metadata)) => {
+                        found.insert(path, metadata);
+                    }
+                    Some(Err(_)) => {}
+                    None => missing.push(path),
+                }
+            }
+        }
+        for path in missing {
+            if let Ok(metadata) = self.lookup(path).await {
+                found.insert(path, metadata);
+            }
+        }
+        found
+    }
+
+    // Ask the databroker for the metadata of a path and cache the result
+    async fn lookup(&self, path: &str) -> Result<v2_proto::Metadata, ClientError> {
+        let result = self.fetch(path).await;
+        let entry = match &result {
+            Ok(metadata) => Entry::Found(metadata.clone()),
+            Err(err) => {
+                warn!(%path, ?err, retry_in = ?RETRY_DELAY, "Getting metadata failed");
+                Entry::Failed {
+                    code: connection::error_code(err),
+                    message: format!("Getting metadata of {path} failed: {err:?}"),
+                    retry_at: Instant::now() + RETRY_DELAY,
+                }
+            }
+        };
+        self.entries.lock().await.insert(path.to_owned(), entry);
+        result
+    }
+
+    async fn fetch(&self, path: &str) -> Result<v2_proto::Metadata, ClientError> {
+        let entries = self
+            .client
+            .lock()
+            .await
+            .list_metadata((path.to_owned(), "*".to_owned()))
+            .await?;
+        // The query matches the children of a branch as well
+        entries
+            .into_iter()
+            .find(|metadata| metadata.path == path)
+            .ok_or_else(|| {
+                ClientError::Status(tonic::Status::not_found(format!("No metadata for {path}")))
+            })
+    }
+}
+
+// Cached metadata or lookup error of a path, None if the databroker has to be asked
+fn cached(
+    entries: &HashMap<String, Entry>,
+    path: &str,
+) -> Option<Result<v2_proto::Metadata, ClientError>> {
+    match entries.get(path)? {
+        Entry::Found(metadata) => Some(Ok(metadata.clone())),
+        Entry::Failed {
+            code,
+            message,
+            retry_at,
+        } if Instant::now() < *retry_at => Some(Err(ClientError::Status(tonic::Status::new(
+            *code,
+            message.clone(),
+        )))),
+        Entry::Failed { .. } => None,
+    }
+}
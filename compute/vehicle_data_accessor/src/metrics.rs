@@ -0,0 +1,91 @@
+// Copyright (c) 2025 Eclipse Foundation and others.
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::connection::Connections;
+use carmate_service::http::Response;
+use carmate_service::metrics::{self, Counters, Histograms};
+
+// Counters and histograms of the data path, exported in the Prometheus text format
+pub(crate) struct Metrics {
+    received: Counters,
+    published: Counters,
+    grpc_errors: Counters,
+    latency: Histograms,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Metrics {
+            received: Counters::new(
+                "vda_messages_received_total",
+                "Datapoints of mapped signals received from the Kuksa databroker",
+                &["signal"],
+            ),
+            // By topic, the VSS path of signal topics or "snapshot" and "status"
+            published: Counters::new(
+                "vda_messages_published_total",
+                "Messages published on uProtocol topics",
+                &["topic"],
+            ),
+            grpc_errors: Counters::new(
+                "vda_grpc_errors_total",
+                "Failed requests and subscriptions to the Kuksa databroker by gRPC status code",
+                &["code"],
+            ),
+            latency: Histograms::new(
+                "vda_publish_latency_seconds",
+                "Time from the source timestamp of a value to its publish",
+                &["signal"],
+            ),
+        }
+    }
+}
+
+impl Metrics {
+    // A datapoint of a mapped signal was received from the databroker
+    pub(crate) fn received(&self, path: &str) {
+        self.received.increment(&[path]);
+    }
+
+    // A message was handed to Zenoh
+    pub(crate) fn published(&self, topic: &str) {
+        self.published.increment(&[topic]);
+    }
+
+    // A databroker request or subscription failed
+    pub(crate) fn grpc_error(&self, code: tonic::Code) {
+        self.grpc_errors.increment(&[&format!("{code:?}")]);
+    }
+
+    // Time from the source timestamp of a value to its publish
+    pub(crate) fn latency(&self, path: &str, seconds: f64) {
+        self.latency.observe(&[path], seconds);
+    }
+
+    // The /metrics endpoint
+    pub(crate) fn response(&self, connections: &Connections) -> Response {
+        let mut out = String::new();
+        self.received.render(&mut out);
+        self.published.render(&mut out);
+        self.grpc_errors.render(&mut out);
+        self.latency.render(&mut out);
+        metrics::render_reconnects(&mut out, "vda_reconnects_total", &connections.all());
+        metrics::response(out)
+    }
+}
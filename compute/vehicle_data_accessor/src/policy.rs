@@ -0,0 +1,355 @@
+// Copyright (c) 2025 Eclipse Foundation and others.
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::mapping::{PublishPolicy, SignalMapping};
+use kuksa_rust_sdk::v2_proto;
+use std::time::{Duration, Instant};
+
+// A value together with its source timestamp in milliseconds since the Unix epoch
+pub(crate) type Sample = (v2_proto::Value, Option<i64>);
+
+// Numeric value of a scalar datapoint, used for deadband checks and downsampling
+pub(crate) fn as_number(value: &v2_proto::Value) -> Option<f64> {
+    match value.typed_value.as_ref()? {
+        v2_proto::value::TypedValue::Int32(value) => Some(f64::from(*value)),
+        v2_proto::value::TypedValue::Int64(value) => Some(*value as f64),
+        v2_proto::value::TypedValue::Uint32(value) => Some(f64::from(*value)),
+        v2_proto::value::TypedValue::Uint64(value) => Some(*value as f64),
+        v2_proto::value::TypedValue::Float(value) => Some(f64::from(*value)),
+        v2_proto::value::TypedValue::Double(value) => Some(*value),
+        _ => None,
+    }
+}
+
+// Whether a new value differs enough from the last published one to be published
+fn is_significant(signal: &SignalMapping, last: &v2_proto::Value, new: &v2_proto::Value) -> bool {
+    if signal.publish == PublishPolicy::Always {
+        return true;
+    }
+    match (as_number(last), as_number(new)) {
+        (Some(last), Some(new))
+            if signal.deadband.is_some() || signal.deadband_relative.is_some() =>
+        {
+            // The larger of the absolute and the relative deadband applies
+            let deadband = signal
+                .deadband
+                .unwrap_or(0.0)
+                .max(signal.deadband_relative.unwrap_or(0.0) * last.abs());
+            (new - last).abs() > deadband
+        }
+        _ => last != new,
+    }
+}
+
+// Publish state of one mapped signal
+#[derive(Default)]
+pub(crate) struct SignalState {
+    // Last published sample and when it was sent
+    last: Option<Sample>,
+    last_sent: Option<Instant>,
+    // Latest significant sample held back by the minimum interval
+    pending: Option<Sample>,
+}
+
+impl SignalState {
+    fn min_interval_elapsed(&self, signal: &SignalMapping, now: Instant) -> bool {
+        match (self.last_sent, signal.min_interval_ms) {
+            (Some(last_sent), Some(min_interval)) => {
+                now.duration_since(last_sent) >= Duration::from_millis(min_interval)
+            }
+            _ => true,
+        }
+    }
+
+    // Handle a new sample from the databroker, returns the sample to publish now if any
+    pub(crate) fn on_sample(
+        &mut self,
+        signal: &SignalMapping,
+        sample: Sample,
+        now: Instant,
+    ) -> Option<Sample> {
+        if let Some((last, _)) = &self.last
+            && !is_significant(signal, last, &sample.0)
+        {
+            // Back within the deadband, a held back change is obsolete
+            self.pending = None;
+            return None;
+        }
+        if !self.min_interval_elapsed(signal, now) {
+            self.pending = Some(sample);
+            return None;
+        }
+        self.pending = None;
+        Some(sample)
+    }
+
+    // Periodic check, returns a held back sample once the minimum interval has passed
+    // or the last sample again once the heartbeat interval has passed
+    pub(crate) fn on_tick(&mut self, signal: &SignalMapping, now: Instant) -> Option<Sample> {
+        if self.pending.is_some() && self.min_interval_elapsed(signal, now) {
+            return self.pending.take();
+        }
+        match (self.last_sent, signal.heartbeat_ms) {
+            (Some(last_sent), Some(heartbeat))
+                if now.duration_since(last_sent) >= Duration::from_millis(heartbeat) =>
+            {
+                self.last.clone()
+            }
+            _ => None,
+        }
+    }
+
+    // Whether a sample is the one published last, e.g. repeated by the heartbeat
+    pub(crate) fn is_last(&self, sample: &Sample) -> bool {
+        self.last.as_ref() == Some(sample)
+    }
+
+    // Record that a sample was published
+    pub(crate) fn sent(&mut self, sample: Sample, now: Instant) {
+        self.last = Some(sample);
+        self.last_sent = Some(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mapping::PayloadFormat;
+
+    fn signal(publish: PublishPolicy) -> SignalMapping {
+        SignalMapping {
+            path: "Vehicle.Speed".to_owned(),
+            resource_id: 0x8001,
+            format: PayloadFormat::Text,
+            publish,
+            deadband: None,
+            deadband_relative: None,
+            min_interval_ms: None,
+            heartbeat_ms: None,
+            stale_after_ms: None,
+        }
+    }
+
+    fn float(value: f32) -> Sample {
+        (
+            v2_proto::Value {
+                typed_value: Some(v2_proto::value::TypedValue::Float(value)),
+            },
+            None,
+        )
+    }
+
+    fn text(value: &str) -> Sample {
+        (
+            v2_proto::Value {
+                typed_value: Some(v2_proto::value::TypedValue::String(value.to_owned())),
+            },
+            None,
+        )
+    }
+
+    // State after a first sample was published
+    fn published(signal: &SignalMapping, sample: Sample, now: Instant) -> SignalState {
+        let mut state = SignalState::default();
+        let sample = state.on_sample(signal, sample, now).expect("first sample");
+        state.sent(sample, now);
+        state
+    }
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn first_sample_is_published() {
+        let signal = SignalMapping {
+            deadband: Some(10.0),
+            ..signal(PublishPolicy::OnChange)
+        };
+        let mut state = SignalState::default();
+        assert_eq!(
+            state.on_sample(&signal, float(1.0), Instant::now()),
+            Some(float(1.0))
+        );
+    }
+
+    #[test]
+    fn always_publishes_repeated_values() {
+        let now = Instant::now();
+        let signal = signal(PublishPolicy::Always);
+        let mut state = published(&signal, float(1.0), now);
+        assert_eq!(state.on_sample(&signal, float(1.0), now), Some(float(1.0)));
+    }
+
+    #[test]
+    fn on_change_without_deadband_compares_values() {
+        let now = Instant::now();
+        let signal = signal(PublishPolicy::OnChange);
+        let mut state = published(&signal, float(1.0), now);
+        assert_eq!(state.on_sample(&signal, float(1.0), now), None);
+        assert_eq!(state.on_sample(&signal, float(1.5), now), Some(float(1.5)));
+    }
+
+    #[test]
+    fn absolute_deadband_edge() {
+        let now = Instant::now();
+        let signal = SignalMapping {
+            deadband: Some(0.5),
+            ..signal(PublishPolicy::OnChange)
+        };
+        let mut state = published(&signal, float(20.0), now);
+        // A change of exactly the deadband is not published, only a larger one
+        assert_eq!(state.on_sample(&signal, float(20.5), now), None);
+        assert_eq!(state.on_sample(&signal, float(19.5), now), None);
+        assert_eq!(
+            state.on_sample(&signal, float(20.75), now),
+            Some(float(20.75))
+        );
+        assert_eq!(
+            state.on_sample(&signal, float(19.25), now),
+            Some(float(19.25))
+        );
+    }
+
+    #[test]
+    fn relative_deadband_edge() {
+        let now = Instant::now();
+        let signal = SignalMapping {
+            deadband_relative: Some(0.25),
+            ..signal(PublishPolicy::OnChange)
+        };
+        // 25 % of the absolute last value, i.e. 2.0
+        let mut state = published(&signal, float(-8.0), now);
+        assert_eq!(state.on_sample(&signal, float(-10.0), now), None);
+        assert_eq!(state.on_sample(&signal, float(-6.0), now), None);
+        assert_eq!(
+            state.on_sample(&signal, float(-10.5), now),
+            Some(float(-10.5))
+        );
+    }
+
+    #[test]
+    fn larger_deadband_applies() {
+        let now = Instant::now();
+        let signal = SignalMapping {
+            deadband: Some(1.0),
+            deadband_relative: Some(0.5),
+            ..signal(PublishPolicy::OnChange)
+        };
+        // The relative deadband of 0.5 is below the absolute one
+        let mut state = published(&signal, float(1.0), now);
+        assert_eq!(state.on_sample(&signal, float(2.0), now), None);
+        assert_eq!(state.on_sample(&signal, float(2.5), now), Some(float(2.5)));
+
+        // The relative deadband of 4.0 is above the absolute one
+        let mut state = published(&signal, float(8.0), now);
+        assert_eq!(state.on_sample(&signal, float(12.0), now), None);
+        assert_eq!(
+            state.on_sample(&signal, float(12.5), now),
+            Some(float(12.5))
+        );
+    }
+
+    #[test]
+    fn deadband_ignores_non_numeric_values() {
+        let now = Instant::now();
+        let signal = SignalMapping {
+            deadband: Some(100.0),
+            ..signal(PublishPolicy::OnChange)
+        };
+        let mut state = published(&signal, text("FF0000"), now);
+        assert_eq!(state.on_sample(&signal, text("FF0000"), now), None);
+        assert_eq!(
+            state.on_sample(&signal, text("00FF00"), now),
+            Some(text("00FF00"))
+        );
+    }
+
+    #[test]
+    fn min_interval_holds_back_until_elapsed() {
+        let start = Instant::now();
+        let signal = SignalMapping {
+            min_interval_ms: Some(1000),
+            ..signal(PublishPolicy::Always)
+        };
+        let mut state = published(&signal, float(1.0), start);
+        assert_eq!(state.on_sample(&signal, float(2.0), start + ms(999)), None);
+        assert_eq!(state.on_tick(&signal, start + ms(999)), None);
+        // Only the latest held back sample is published once the interval passed
+        assert_eq!(state.on_sample(&signal, float(3.0), start + ms(999)), None);
+        assert_eq!(state.on_tick(&signal, start + ms(1000)), Some(float(3.0)));
+        assert_eq!(state.on_tick(&signal, start + ms(1000)), None);
+    }
+
+    #[test]
+    fn min_interval_elapsed_publishes_immediately() {
+        let start = Instant::now();
+        let signal = SignalMapping {
+            min_interval_ms: Some(1000),
+            ..signal(PublishPolicy::Always)
+        };
+        let mut state = published(&signal, float(1.0), start);
+        assert_eq!(
+            state.on_sample(&signal, float(2.0), start + ms(1000)),
+            Some(float(2.0))
+        );
+    }
+
+    #[test]
+    fn return_into_deadband_drops_held_back_sample() {
+        let start = Instant::now();
+        let signal = SignalMapping {
+            deadband: Some(1.0),
+            min_interval_ms: Some(1000),
+            ..signal(PublishPolicy::OnChange)
+        };
+        let mut state = published(&signal, float(10.0), start);
+        assert_eq!(state.on_sample(&signal, float(12.0), start + ms(100)), None);
+        assert_eq!(state.on_sample(&signal, float(10.5), start + ms(200)), None);
+        assert_eq!(state.on_tick(&signal, start + ms(1000)), None);
+    }
+
+    #[test]
+    fn heartbeat_repeats_last_sample_when_due() {
+        let start = Instant::now();
+        let signal = SignalMapping {
+            heartbeat_ms: Some(5000),
+            ..signal(PublishPolicy::OnChange)
+        };
+        let mut state = published(&signal, float(1.0), start);
+        assert_eq!(state.on_tick(&signal, start + ms(4999)), None);
+        assert_eq!(state.on_tick(&signal, start + ms(5000)), Some(float(1.0)));
+        assert!(state.is_last(&float(1.0)));
+
+        // The heartbeat interval restarts with the repeated publish
+        state.sent(float(1.0), start + ms(5000));
+        assert_eq!(state.on_tick(&signal, start + ms(9999)), None);
+        assert_eq!(state.on_tick(&signal, start + ms(10000)), Some(float(1.0)));
+    }
+
+    #[test]
+    fn no_heartbeat_before_first_publish() {
+        let signal = SignalMapping {
+            heartbeat_ms: Some(0),
+            ..signal(PublishPolicy::Always)
+        };
+        let mut state = SignalState::default();
+        assert_eq!(state.on_tick(&signal, Instant::now()), None);
+    }
+}
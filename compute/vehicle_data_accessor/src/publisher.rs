@@ -0,0 +1,488 @@
+// Copyright (c) 2025 Eclipse Foundation and others.
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::DisplayDatapoint;
+use crate::connection::Connections;
+use crate::history::History;
+use crate::json;
+use crate::mapping::{Mapping, PayloadFormat, SignalMapping, SnapshotConfig, StatusConfig};
+use crate::metadata::MetadataCache;
+use crate::metrics::Metrics;
+use crate::policy::{Sample, SignalState};
+use crate::recorder::Recorder;
+use crate::snapshot::Snapshot;
+use crate::status::{self, SignalStatus, StatusMessage};
+use kuksa_rust_sdk::v2_proto;
+use prost::Message;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{Instrument, debug, info, info_span, warn};
+use up_rust::LocalUriProvider;
+use up_rust::StaticUriProvider;
+use up_rust::UMessage;
+use up_rust::UMessageBuilder;
+use up_rust::UPayloadFormat;
+use up_rust::UTransport;
+use up_rust::UUID;
+use up_transport_zenoh::UPTransportZenoh;
+
+// Type URL of protobuf payloads, consumers unpack it from google.protobuf.Any
+const VALUE_TYPE_URL: &str = "type.googleapis.com/kuksa.val.v2.Value";
+
+// What happens to messages that cannot be sent while Zenoh is disconnected
+#[derive(clap::ValueEnum, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum OfflinePolicy {
+    // Keep the latest message per topic and send it once Zenoh is back
+    #[default]
+    KeepLatest,
+    // Discard the message
+    Drop,
+}
+
+// Forwards datapoints of mapped signals from the databroker to uProtocol
+pub(crate) struct Publisher {
+    transport: Arc<UPTransportZenoh>,
+    uri_provider: Arc<StaticUriProvider>,
+    metadata: Arc<MetadataCache>,
+    connections: Arc<Connections>,
+    offline_policy: OfflinePolicy,
+    // Messages not sent yet because Zenoh was disconnected, by topic resource id
+    pending: HashMap<u16, UMessage>,
+    // Publish state per VSS path, used by the publish policies
+    states: HashMap<String, SignalState>,
+    snapshot_config: Option<SnapshotConfig>,
+    snapshot: Snapshot,
+    last_snapshot: Option<Instant>,
+    status_config: Option<StatusConfig>,
+    // Status per VSS path, signals without an entry are not available yet
+    statuses: HashMap<String, SignalStatus>,
+    history: Arc<History>,
+    recorder: Option<Recorder>,
+    metrics: Arc<Metrics>,
+    // Metric label per topic resource id
+    topics: HashMap<u16, String>,
+}
+
+impl Publisher {
+    pub(crate) fn new(
+        transport: Arc<UPTransportZenoh>,
+        uri_provider: Arc<StaticUriProvider>,
+        metadata: Arc<MetadataCache>,
+        connections: Arc<Connections>,
+        offline_policy: OfflinePolicy,
+        mapping: &Mapping,
+        history: Arc<History>,
+    ) -> Self {
+        let mut topics: HashMap<u16, String> = mapping
+            .signals
+            .iter()
+            .map(|signal| (signal.resource_id, signal.path.clone()))
+            .collect();
+        if let Some(config) = &mapping.snapshot {
+            topics.insert(config.resource_id, "snapshot".to_owned());
+        }
+        if let Some(config) = &mapping.status {
+            topics.insert(config.resource_id, "status".to_owned());
+        }
+        Publisher {
+            transport,
+            uri_provider,
+            metadata,
+            connections,
+            offline_policy,
+            pending: HashMap::new(),
+            states: HashMap::new(),
+            snapshot_config: mapping.snapshot.clone(),
+            snapshot: Snapshot::default(),
+            last_snapshot: None,
+            status_config: mapping.status.clone(),
+            statuses: HashMap::new(),
+            history,
+            recorder: None,
+            metrics: Arc::default(),
+            topics,
+        }
+    }
+
+    // Count messages and latencies in the given metrics
+    pub(crate) fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    // Additionally record every signal change to disk
+    pub(crate) fn with_recorder(mut self, recorder: Recorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    // Handle a datapoint of a mapped signal according to its publish policy,
+    // everything logged meanwhile carries the path and a correlation id
+    pub(crate) async fn handle(
+        &mut self,
+        signal: &SignalMapping,
+        datapoint: v2_proto::Datapoint,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let span = info_span!(
+            "datapoint",
+            source = "kuksa",
+            path = %signal.path,
+            correlation_id = %UUID::build().to_hyphenated_string(),
+        );
+        self.handle_datapoint(signal, datapoint)
+            .instrument(span)
+            .await
+    }
+
+    async fn handle_datapoint(
+        &mut self,
+        signal: &SignalMapping,
+        datapoint: v2_proto::Datapoint,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let timestamp = json::timestamp_millis(&datapoint);
+        debug!(value = ?datapoint.value, timestamp, "Received datapoint");
+        self.metrics.received(&signal.path);
+        match datapoint.value {
+            Some(value) => {
+                let now = Instant::now();
+                self.snapshot
+                    .update(&signal.path, (value.clone(), timestamp), now);
+                self.history
+                    .record(
+                        &signal.path,
+                        timestamp.unwrap_or_else(json::now_millis),
+                        value.clone(),
+                    )
+                    .await;
+                if let Some(recorder) = &mut self.recorder
+                    && let Err(err) = recorder
+                        .record(&signal.path, &(value.clone(), timestamp))
+                        .await
+                {
+                    warn!(?err, "Recording failed");
+                }
+                if status::is_stale(signal, timestamp, now, now) {
+                    // Kept in the snapshot, but not published as a current value
+                    return self.set_status(signal, SignalStatus::Stale).await;
+                }
+                self.set_status(signal, SignalStatus::Valid).await?;
+                let due = self
+                    .states
+                    .entry(signal.path.clone())
+                    .or_default()
+                    .on_sample(signal, (value, timestamp), now);
+                if let Some(sample) = due {
+                    self.publish_sample(signal, sample).await?;
+                }
+            }
+            None => {
+                self.snapshot.remove(&signal.path);
+                self.set_status(signal, SignalStatus::NotAvailable).await?;
+            }
+        }
+        Ok(())
+    }
+
+    // Report all signals as unreliable while the databroker is not reachable
+    pub(crate) async fn broker_error(
+        &mut self,
+        signals: &[SignalMapping],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        for signal in signals {
+            self.set_status(signal, SignalStatus::BrokerError).await?;
+        }
+        Ok(())
+    }
+
+    // Record the status of a signal and publish it if it changed
+    async fn set_status(
+        &mut self,
+        signal: &SignalMapping,
+        status: SignalStatus,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if self.statuses.insert(signal.path.clone(), status) == Some(status) {
+            return Ok(());
+        }
+        info!(path = %signal.path, ?status, "Signal status changed");
+
+        if status != SignalStatus::Valid {
+            // Start over, so the first valid value afterwards is published regardless of policy
+            self.states.remove(&signal.path);
+            // JSON consumers get the status on the signal topic itself, with the last value if any
+            if signal.format == PayloadFormat::Json {
+                let (value, timestamp) = match self.snapshot.get(&signal.path) {
+                    Some(((value, timestamp), _)) => (Some(value.clone()), *timestamp),
+                    None => (None, None),
+                };
+                let payload = self
+                    .json_payload(signal, value.as_ref(), timestamp, status)
+                    .await?;
+                let message = UMessageBuilder::publish(
+                    self.uri_provider.get_resource_uri(signal.resource_id),
+                )
+                .build_with_payload(payload, UPayloadFormat::UPAYLOAD_FORMAT_JSON)?;
+                self.send(signal.resource_id, message).await;
+            }
+        }
+
+        if let Some(config) = self.status_config.clone() {
+            let message =
+                UMessageBuilder::publish(self.uri_provider.get_resource_uri(config.resource_id))
+                    .build_with_payload(
+                        serde_json::to_vec(&StatusMessage::new(&signal.path, status))?,
+                        UPayloadFormat::UPAYLOAD_FORMAT_JSON,
+                    )?;
+            self.send(config.resource_id, message).await;
+        }
+        Ok(())
+    }
+
+    // Mark signals without a recent value as stale and
+    // publish held back changes and heartbeats that are due
+    pub(crate) async fn tick(
+        &mut self,
+        signals: &[SignalMapping],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let now = Instant::now();
+        for signal in signals {
+            if self.statuses.get(&signal.path) != Some(&SignalStatus::Valid) {
+                // Old values are not repeated by heartbeats
+                continue;
+            }
+            if self
+                .snapshot
+                .get(&signal.path)
+                .is_some_and(|((_, timestamp), received)| {
+                    status::is_stale(signal, *timestamp, *received, now)
+                })
+            {
+                self.set_status(signal, SignalStatus::Stale).await?;
+                continue;
+            }
+            let due = self
+                .states
+                .get_mut(&signal.path)
+                .and_then(|state| state.on_tick(signal, now));
+            if let Some(sample) = due {
+                self.publish_sample(signal, sample).await?;
+            }
+        }
+        self.publish_snapshot(signals).await
+    }
+
+    // Publish the aggregated vehicle context once its interval has passed
+    async fn publish_snapshot(
+        &mut self,
+        signals: &[SignalMapping],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(config) = self.snapshot_config.clone() else {
+            return Ok(());
+        };
+        let now = Instant::now();
+        if self.last_snapshot.is_some_and(|last| {
+            now.duration_since(last) < Duration::from_millis(config.interval_ms)
+        }) {
+            return Ok(());
+        }
+        self.last_snapshot = Some(now);
+
+        let snapshot = self
+            .snapshot
+            .to_message(signals, &self.statuses, &self.metadata, now)
+            .await;
+        let message =
+            UMessageBuilder::publish(self.uri_provider.get_resource_uri(config.resource_id))
+                .build_with_payload(
+                    serde_json::to_vec(&snapshot)?,
+                    UPayloadFormat::UPAYLOAD_FORMAT_JSON,
+                )?;
+        self.send(config.resource_id, message).await;
+        Ok(())
+    }
+
+    async fn publish_sample(
+        &mut self,
+        signal: &SignalMapping,
+        sample: Sample,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // Heartbeats repeat an old value, their age is no publish latency
+        let repeated = self
+            .states
+            .get(&signal.path)
+            .is_some_and(|state| state.is_last(&sample));
+        let (value, timestamp) = sample.clone();
+        if !self.publish(signal, value, timestamp).await? {
+            return Ok(());
+        }
+        // Messages kept by the offline policy are not published yet
+        if !repeated
+            && self.pending.is_empty()
+            && let Some(timestamp) = timestamp
+        {
+            let latency = (json::now_millis() - timestamp).max(0) as f64 / 1000.0;
+            self.metrics.latency(&signal.path, latency);
+        }
+        if let Some(state) = self.states.get_mut(&signal.path) {
+            state.sent(sample, Instant::now());
+        }
+        Ok(())
+    }
+
+    // Publish the value of a mapped signal on its uProtocol topic,
+    // returns false if the message was dropped
+    async fn publish(
+        &mut self,
+        signal: &SignalMapping,
+        value: v2_proto::Value,
+        timestamp: Option<i64>,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        let topic = self.uri_provider.get_resource_uri(signal.resource_id);
+        let message = match signal.format {
+            PayloadFormat::Text => {
+                let printable = DisplayDatapoint(value);
+                UMessageBuilder::publish(topic).build_with_payload(
+                    printable.to_string(),
+                    UPayloadFormat::UPAYLOAD_FORMAT_TEXT,
+                )?
+            }
+            PayloadFormat::Protobuf => {
+                let any = prost_types::Any {
+                    type_url: VALUE_TYPE_URL.to_owned(),
+                    value: value.encode_to_vec(),
+                };
+                UMessageBuilder::publish(topic).build_with_payload(
+                    any.encode_to_vec(),
+                    UPayloadFormat::UPAYLOAD_FORMAT_PROTOBUF_WRAPPED_IN_ANY,
+                )?
+            }
+            PayloadFormat::Json => UMessageBuilder::publish(topic).build_with_payload(
+                self.json_payload(signal, Some(&value), timestamp, SignalStatus::Valid)
+                    .await?,
+                UPayloadFormat::UPAYLOAD_FORMAT_JSON,
+            )?,
+        };
+        // The message id lets consumers correlate the message with this log
+        debug!(
+            path = %signal.path,
+            resource_id = signal.resource_id,
+            message_id = %message
+                .attributes
+                .id
+                .as_ref()
+                .map(UUID::to_hyphenated_string)
+                .unwrap_or_default(),
+            "Publishing value"
+        );
+        Ok(self.send(signal.resource_id, message).await)
+    }
+
+    // JSON payload of a signal with its VSS metadata and status
+    async fn json_payload(
+        &self,
+        signal: &SignalMapping,
+        value: Option<&v2_proto::Value>,
+        timestamp: Option<i64>,
+        status: SignalStatus,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        // Publish without metadata rather than not at all, the cache logs the failure
+        // and retries after a delay
+        let metadata = self.metadata.get(&signal.path).await.ok();
+        let message =
+            json::SignalMessage::new(&signal.path, value, metadata.as_ref(), timestamp, status);
+        Ok(serde_json::to_vec(&message)?)
+    }
+
+    // Send a message, applying the offline policy if Zenoh is disconnected
+    async fn send(&mut self, resource_id: u16, message: UMessage) -> bool {
+        if !self.pending.is_empty() {
+            // Still disconnected, a newer message replaces the pending one of the same topic
+            self.pending.insert(resource_id, message);
+            self.flush_pending().await;
+            return true;
+        }
+
+        match self.transport.send(message.clone()).await {
+            Ok(()) => {
+                self.connections.zenoh.succeeded();
+                self.count_published(resource_id);
+                true
+            }
+            Err(status) => {
+                warn!(resource_id, ?status, "Sending message failed");
+                self.connections.zenoh.failed();
+                match self.offline_policy {
+                    OfflinePolicy::KeepLatest => {
+                        self.pending.insert(resource_id, message);
+                        true
+                    }
+                    OfflinePolicy::Drop => false,
+                }
+            }
+        }
+    }
+
+    // Try to send the pending messages until the timeout, e.g. on shutdown
+    pub(crate) async fn drain(&mut self, timeout: Duration) {
+        if self.pending.is_empty() {
+            return;
+        }
+        info!(pending = self.pending.len(), "Sending pending messages");
+        let deadline = Instant::now() + timeout;
+        while !self.pending.is_empty() && Instant::now() < deadline {
+            self.flush_pending().await;
+            if !self.pending.is_empty() {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        }
+        if !self.pending.is_empty() {
+            warn!(pending = self.pending.len(), "Dropping pending messages");
+        }
+    }
+
+    // Send messages kept while Zenoh was disconnected, stopping at the first failure
+    async fn flush_pending(&mut self) {
+        let resource_ids: Vec<u16> = self.pending.keys().copied().collect();
+        for resource_id in resource_ids {
+            let Some(message) = self.pending.remove(&resource_id) else {
+                continue;
+            };
+            if let Err(status) = self.transport.send(message.clone()).await {
+                warn!(
+                    pending = self.pending.len() + 1,
+                    ?status,
+                    "Zenoh still unavailable"
+                );
+                self.pending.insert(resource_id, message);
+                self.connections.zenoh.failed();
+                return;
+            }
+            self.count_published(resource_id);
+        }
+        self.connections.zenoh.succeeded();
+    }
+
+    fn count_published(&self, resource_id: u16) {
+        if let Some(topic) = self.topics.get(&resource_id) {
+            self.metrics.published(topic);
+        }
+    }
+}
@@ -0,0 +1,137 @@
+// Copyright (c) 2025 Eclipse Foundation and others.
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::json;
+use crate::policy::Sample;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tokio::fs::{self, File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tracing::info;
+
+// Recordings are named signals-<start in ms since the Unix epoch>.jsonl, so they sort by time
+const FILE_PREFIX: &str = "signals-";
+const FILE_SUFFIX: &str = ".jsonl";
+
+// Where and how much to record
+#[derive(Debug, Clone)]
+pub(crate) struct RecorderOptions {
+    pub dir: PathBuf,
+    // A new file is started once the current one would exceed this size in bytes
+    pub max_file_size: u64,
+    // The oldest files are deleted beyond this number
+    pub max_files: usize,
+}
+
+// One line of a recording
+#[derive(Serialize, Debug)]
+struct RecordLine<'a> {
+    // Source timestamp, or time of receipt if there is none, in milliseconds since the Unix epoch
+    timestamp: i64,
+    path: &'a str,
+    // VSS datatype of the value, so it can be replayed with the same type
+    datatype: Option<String>,
+    value: serde_json::Value,
+}
+
+// Writes the signal changes as JSON lines into size limited, rotating files
+pub(crate) struct Recorder {
+    options: RecorderOptions,
+    // Current file and its size
+    file: Option<(File, u64)>,
+    // Last recorded sample per VSS path
+    last: HashMap<String, Sample>,
+}
+
+impl Recorder {
+    pub(crate) async fn new(options: RecorderOptions) -> std::io::Result<Self> {
+        fs::create_dir_all(&options.dir).await?;
+        Ok(Recorder {
+            options,
+            file: None,
+            last: HashMap::new(),
+        })
+    }
+
+    // Append a sample, unless it is the last recorded one of the signal read again
+    pub(crate) async fn record(&mut self, path: &str, sample: &Sample) -> std::io::Result<()> {
+        if self.last.get(path) == Some(sample) {
+            return Ok(());
+        }
+        self.last.insert(path.to_owned(), sample.clone());
+
+        let (value, timestamp) = sample;
+        let line = RecordLine {
+            timestamp: timestamp.unwrap_or_else(json::now_millis),
+            path,
+            datatype: json::value_data_type(value)
+                .and_then(|data_type| json::data_type_name(data_type as i32)),
+            value: json::value_to_json(value),
+        };
+        let mut bytes = serde_json::to_vec(&line)?;
+        bytes.push(b'\n');
+        let length = bytes.len() as u64;
+
+        let rotate = match &self.file {
+            Some((_, size)) => *size > 0 && size + length > self.options.max_file_size,
+            None => true,
+        };
+        if rotate {
+            self.file = Some((self.open_next().await?, 0));
+        }
+        if let Some((file, size)) = &mut self.file {
+            // Flushed per line, so a crash loses nothing that was reported
+            file.write_all(&bytes).await?;
+            file.flush().await?;
+            *size += length;
+        }
+        Ok(())
+    }
+
+    // Start a new file and delete the oldest ones beyond the limit
+    async fn open_next(&self) -> std::io::Result<File> {
+        let path = self.options.dir.join(format!(
+            "{FILE_PREFIX}{:013}{FILE_SUFFIX}",
+            json::now_millis()
+        ));
+        info!(path = %path.display(), "Recording signals");
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await?;
+
+        let mut recordings = Vec::new();
+        let mut entries = fs::read_dir(&self.options.dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.starts_with(FILE_PREFIX) && name.ends_with(FILE_SUFFIX) {
+                recordings.push(entry.path());
+            }
+        }
+        recordings.sort();
+        let excess = recordings.len().saturating_sub(self.options.max_files);
+        for old in &recordings[..excess] {
+            info!(path = %old.display(), "Deleting old recording");
+            fs::remove_file(old).await?;
+        }
+        Ok(file)
+    }
+}
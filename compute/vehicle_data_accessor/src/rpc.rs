@@ -0,0 +1,470 @@
+// Copyright (c) 2025 Eclipse Foundation and others.
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::history::{self, Bucket, History};
+use crate::json;
+use crate::mapping::ActuatorRule;
+use crate::metadata::MetadataCache;
+use async_trait::async_trait;
+use kuksa_rust_sdk::kuksa::common::{ClientError, ClientTraitV2};
+use kuksa_rust_sdk::kuksa::val::v2::KuksaClientV2;
+use kuksa_rust_sdk::v2_proto;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::info;
+use up_rust::UAttributes;
+use up_rust::UPayloadFormat;
+use up_rust::UUID;
+use up_rust::communication::{RequestHandler, ServiceInvocationError, UPayload};
+
+// uProtocol method resource ids of the vehicledataaccessor entity
+pub(crate) const METHOD_GET_SIGNAL: u16 = 0x0001;
+pub(crate) const METHOD_SET_ACTUATOR: u16 = 0x0002;
+pub(crate) const METHOD_GET_HISTORY: u16 = 0x0003;
+
+// Upper limit of buckets per GetHistory response
+const MAX_BUCKETS: i64 = 1000;
+
+#[derive(Deserialize, Debug)]
+struct GetSignalRequest {
+    paths: Vec<String>,
+}
+
+#[derive(Serialize, Debug)]
+struct SignalValue {
+    path: String,
+    value: serde_json::Value,
+    unit: String,
+    // Source timestamp in milliseconds since the Unix epoch
+    timestamp: Option<i64>,
+}
+
+#[derive(Serialize, Debug)]
+struct GetSignalResponse {
+    signals: Vec<SignalValue>,
+}
+
+#[derive(Deserialize, Debug)]
+struct SetActuatorRequest {
+    path: String,
+    value: serde_json::Value,
+}
+
+// Outcome of a SetActuator request, so the agent can confirm or apologize
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+enum SetActuatorResult {
+    // The target value was handed to the databroker
+    Accepted,
+    // The actuator is not in the allow-list
+    NotAllowed,
+    // The value does not match the VSS datatype of the actuator
+    InvalidValue,
+    // The value is outside of the configured range or allowed values
+    OutOfRange,
+}
+
+#[derive(Serialize, Debug)]
+struct SetActuatorResponse {
+    path: String,
+    value: serde_json::Value,
+    result: SetActuatorResult,
+    message: String,
+}
+
+// Time range in milliseconds since the Unix epoch, by default the whole retention
+#[derive(Deserialize, Debug)]
+struct GetHistoryRequest {
+    path: String,
+    from: Option<i64>,
+    to: Option<i64>,
+    // Aggregate into min/max/avg per bucket of this width instead of returning all samples
+    bucket_ms: Option<u64>,
+}
+
+#[derive(Serialize, Debug)]
+struct HistoryValue {
+    timestamp: i64,
+    value: serde_json::Value,
+}
+
+// Either the raw samples or the buckets are set, depending on bucket_ms of the request
+#[derive(Serialize, Debug)]
+struct GetHistoryResponse {
+    path: String,
+    unit: String,
+    from: i64,
+    to: i64,
+    samples: Option<Vec<HistoryValue>>,
+    buckets: Option<Vec<Bucket>>,
+}
+
+// Map a Kuksa client error onto the matching uProtocol error code
+fn to_service_error(err: ClientError) -> ServiceInvocationError {
+    match err {
+        ClientError::Connection(message) => ServiceInvocationError::Unavailable(format!(
+            "Kuksa databroker not reachable: {message}"
+        )),
+        ClientError::Status(status) => {
+            let message = status.message().to_owned();
+            match status.code() {
+                tonic::Code::NotFound => ServiceInvocationError::NotFound(message),
+                tonic::Code::InvalidArgument => ServiceInvocationError::InvalidArgument(message),
+                tonic::Code::PermissionDenied => ServiceInvocationError::PermissionDenied(message),
+                tonic::Code::Unavailable => ServiceInvocationError::Unavailable(message),
+                _ => ServiceInvocationError::Internal(format!("{:?}: {message}", status.code())),
+            }
+        }
+        ClientError::Function(errors) => ServiceInvocationError::Internal(format!("{errors:?}")),
+    }
+}
+
+// Parse a JSON encoded request payload
+fn parse_request<T: DeserializeOwned>(
+    request_payload: Option<UPayload>,
+) -> Result<T, ServiceInvocationError> {
+    let payload = request_payload.ok_or_else(|| {
+        ServiceInvocationError::InvalidArgument("Request has no payload".to_owned())
+    })?;
+    if payload.payload_format() != UPayloadFormat::UPAYLOAD_FORMAT_JSON {
+        return Err(ServiceInvocationError::InvalidArgument(format!(
+            "Unsupported payload format {:?}, expected JSON",
+            payload.payload_format()
+        )));
+    }
+    serde_json::from_slice(&payload.payload())
+        .map_err(|err| ServiceInvocationError::InvalidArgument(format!("Invalid request: {err}")))
+}
+
+// Id of the request message, to correlate the request with the log of the caller
+fn request_id(attributes: &UAttributes) -> String {
+    attributes
+        .id
+        .as_ref()
+        .map(UUID::to_hyphenated_string)
+        .unwrap_or_default()
+}
+
+// Encode a response as JSON payload
+fn json_response<T: Serialize>(response: &T) -> Result<Option<UPayload>, ServiceInvocationError> {
+    let bytes = serde_json::to_vec(response)
+        .map_err(|err| ServiceInvocationError::Internal(format!("Invalid response: {err}")))?;
+    Ok(Some(UPayload::new(
+        bytes,
+        UPayloadFormat::UPAYLOAD_FORMAT_JSON,
+    )))
+}
+
+// Returns current value, unit and timestamp of the requested VSS paths
+pub(crate) struct GetSignalHandler {
+    client: Arc<Mutex<KuksaClientV2>>,
+    metadata: Arc<MetadataCache>,
+}
+
+impl GetSignalHandler {
+    pub(crate) fn new(client: Arc<Mutex<KuksaClientV2>>, metadata: Arc<MetadataCache>) -> Self {
+        GetSignalHandler { client, metadata }
+    }
+}
+
+#[async_trait]
+impl RequestHandler for GetSignalHandler {
+    async fn handle_request(
+        &self,
+        _resource_id: u16,
+        message_attributes: &UAttributes,
+        request_payload: Option<UPayload>,
+    ) -> Result<Option<UPayload>, ServiceInvocationError> {
+        let request: GetSignalRequest = parse_request(request_payload)?;
+        if request.paths.is_empty() {
+            return Err(ServiceInvocationError::InvalidArgument(
+                "No VSS paths requested".to_owned(),
+            ));
+        }
+        info!(
+            request_id = %request_id(message_attributes),
+            paths = ?request.paths,
+            "GetSignal request"
+        );
+
+        // Resolving the metadata first rejects unknown paths with NOT_FOUND
+        let mut units = Vec::with_capacity(request.paths.len());
+        for path in &request.paths {
+            let metadata = self.metadata.get(path).await.map_err(to_service_error)?;
+            units.push(metadata.unit);
+        }
+
+        let datapoints = self
+            .client
+            .lock()
+            .await
+            .get_values(request.paths.clone())
+            .await
+            .map_err(to_service_error)?;
+
+        let signals = request
+            .paths
+            .into_iter()
+            .zip(units)
+            .zip(datapoints)
+            .map(|((path, unit), datapoint)| SignalValue {
+                path,
+                value: datapoint
+                    .value
+                    .as_ref()
+                    .map(json::value_to_json)
+                    .unwrap_or_default(),
+                unit,
+                timestamp: json::timestamp_millis(&datapoint),
+            })
+            .collect();
+
+        json_response(&GetSignalResponse { signals })
+    }
+}
+
+// Sets target values of allow-listed VSS actuators
+pub(crate) struct SetActuatorHandler {
+    client: Arc<Mutex<KuksaClientV2>>,
+    metadata: Arc<MetadataCache>,
+    actuators: HashMap<String, ActuatorRule>,
+}
+
+impl SetActuatorHandler {
+    pub(crate) fn new(
+        client: Arc<Mutex<KuksaClientV2>>,
+        metadata: Arc<MetadataCache>,
+        actuators: &[ActuatorRule],
+    ) -> Self {
+        SetActuatorHandler {
+            client,
+            metadata,
+            actuators: actuators
+                .iter()
+                .map(|rule| (rule.path.clone(), rule.clone()))
+                .collect(),
+        }
+    }
+
+    // Check a requested target value against the VSS datatype and the configured limits
+    async fn check(
+        &self,
+        rule: &ActuatorRule,
+        json_value: &serde_json::Value,
+    ) -> Result<Result<v2_proto::Value, (SetActuatorResult, String)>, ServiceInvocationError> {
+        let metadata = self
+            .metadata
+            .get(&rule.path)
+            .await
+            .map_err(to_service_error)?;
+        let data_type = v2_proto::DataType::try_from(metadata.data_type).map_err(|_| {
+            ServiceInvocationError::Internal(format!(
+                "Unknown VSS datatype {} of {}",
+                metadata.data_type, rule.path
+            ))
+        })?;
+
+        let value = match json::value_from_json(json_value, data_type) {
+            Ok(value) => value,
+            Err(message) => return Ok(Err((SetActuatorResult::InvalidValue, message))),
+        };
+
+        if let Some(number) = json_value.as_f64()
+            && (rule.min.is_some_and(|min| number < min)
+                || rule.max.is_some_and(|max| number > max))
+        {
+            return Ok(Err((
+                SetActuatorResult::OutOfRange,
+                format!(
+                    "{number} is outside of {}..={}",
+                    rule.min.map_or(String::new(), |min| min.to_string()),
+                    rule.max.map_or(String::new(), |max| max.to_string())
+                ),
+            )));
+        }
+        if let (Some(text), Some(allowed)) = (json_value.as_str(), &rule.allowed)
+            && !allowed.iter().any(|candidate| candidate == text)
+        {
+            return Ok(Err((
+                SetActuatorResult::OutOfRange,
+                format!("{text} is not one of {allowed:?}"),
+            )));
+        }
+
+        Ok(Ok(value))
+    }
+}
+
+#[async_trait]
+impl RequestHandler for SetActuatorHandler {
+    async fn handle_request(
+        &self,
+        _resource_id: u16,
+        message_attributes: &UAttributes,
+        request_payload: Option<UPayload>,
+    ) -> Result<Option<UPayload>, ServiceInvocationError> {
+        let request: SetActuatorRequest = parse_request(request_payload)?;
+        let request_id = request_id(message_attributes);
+        info!(
+            %request_id,
+            path = %request.path,
+            value = %request.value,
+            "SetActuator request"
+        );
+
+        let (result, message) = match self.actuators.get(&request.path) {
+            None => (
+                SetActuatorResult::NotAllowed,
+                format!("{} is not a writable actuator", request.path),
+            ),
+            Some(rule) => match self.check(rule, &request.value).await? {
+                Err(rejection) => rejection,
+                Ok(value) => {
+                    self.client
+                        .lock()
+                        .await
+                        .actuate(request.path.clone(), value)
+                        .await
+                        .map_err(to_service_error)?;
+                    (SetActuatorResult::Accepted, String::new())
+                }
+            },
+        };
+        info!(
+            %request_id,
+            path = %request.path,
+            ?result,
+            %message,
+            "SetActuator result"
+        );
+
+        json_response(&SetActuatorResponse {
+            path: request.path,
+            value: request.value,
+            result,
+            message,
+        })
+    }
+}
+
+// Returns the recent values of a mapped signal, optionally downsampled
+pub(crate) struct GetHistoryHandler {
+    history: Arc<History>,
+    metadata: Arc<MetadataCache>,
+}
+
+impl GetHistoryHandler {
+    pub(crate) fn new(history: Arc<History>, metadata: Arc<MetadataCache>) -> Self {
+        GetHistoryHandler { history, metadata }
+    }
+}
+
+#[async_trait]
+impl RequestHandler for GetHistoryHandler {
+    async fn handle_request(
+        &self,
+        _resource_id: u16,
+        message_attributes: &UAttributes,
+        request_payload: Option<UPayload>,
+    ) -> Result<Option<UPayload>, ServiceInvocationError> {
+        let request: GetHistoryRequest = parse_request(request_payload)?;
+        info!(
+            request_id = %request_id(message_attributes),
+            path = %request.path,
+            from = request.from,
+            to = request.to,
+            bucket_ms = request.bucket_ms,
+            "GetHistory request"
+        );
+
+        let to = request.to.unwrap_or_else(json::now_millis);
+        let from = request
+            .from
+            .unwrap_or_else(|| to.saturating_sub(self.history.retention_ms()));
+        // Client supplied bounds far apart do not fit into an i64
+        let span = match to.checked_sub(from) {
+            Some(span) if span >= 0 => span,
+            Some(_) => {
+                return Err(ServiceInvocationError::InvalidArgument(format!(
+                    "Start {from} is after end {to}"
+                )));
+            }
+            None => {
+                return Err(ServiceInvocationError::InvalidArgument(format!(
+                    "Range from {from} to {to} is too large"
+                )));
+            }
+        };
+
+        let samples = self
+            .history
+            .range(&request.path, from, to)
+            .await
+            .ok_or_else(|| {
+                ServiceInvocationError::NotFound(format!(
+                    "{} is not a mapped signal, no history is kept",
+                    request.path
+                ))
+            })?;
+        // The unit is informational only, the history is answered without it
+        let unit = self
+            .metadata
+            .get(&request.path)
+            .await
+            .map(|metadata| metadata.unit)
+            .unwrap_or_default();
+
+        let (samples, buckets) = match request.bucket_ms {
+            None => (
+                Some(
+                    samples
+                        .into_iter()
+                        .map(|sample| HistoryValue {
+                            timestamp: sample.timestamp,
+                            value: json::value_to_json(&sample.value),
+                        })
+                        .collect(),
+                ),
+                None,
+            ),
+            Some(bucket_ms) => {
+                let bucket_ms = i64::try_from(bucket_ms).unwrap_or(i64::MAX);
+                if bucket_ms == 0 || span / bucket_ms >= MAX_BUCKETS {
+                    return Err(ServiceInvocationError::InvalidArgument(format!(
+                        "Bucket width must be greater than 0 and give at most {MAX_BUCKETS} buckets"
+                    )));
+                }
+                let buckets = history::downsample(&samples, from, bucket_ms)
+                    .map_err(ServiceInvocationError::InvalidArgument)?;
+                (None, Some(buckets))
+            }
+        };
+
+        json_response(&GetHistoryResponse {
+            path: request.path,
+            unit,
+            from,
+            to,
+            samples,
+            buckets,
+        })
+    }
+}
@@ -0,0 +1,111 @@
+// Copyright (c) 2025 Eclipse Foundation and others.
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::json;
+use crate::mapping::SignalMapping;
+use crate::metadata::MetadataCache;
+use crate::policy::Sample;
+use crate::status::SignalStatus;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Instant;
+
+#[derive(Serialize, Debug)]
+struct SnapshotField {
+    value: serde_json::Value,
+    unit: Option<String>,
+    // Source timestamp in milliseconds since the Unix epoch
+    timestamp: Option<i64>,
+    // Time since the value was received from the databroker
+    age_ms: Option<u128>,
+    status: SignalStatus,
+}
+
+// Vehicle context with one field per mapped signal, ordered by VSS path
+#[derive(Serialize, Debug)]
+pub(crate) struct SnapshotMessage {
+    // Creation time in milliseconds since the Unix epoch
+    timestamp: i64,
+    signals: BTreeMap<String, SnapshotField>,
+}
+
+// Latest sample of every mapped signal, independent of its publish policy
+#[derive(Default)]
+pub(crate) struct Snapshot {
+    latest: HashMap<String, (Sample, Instant)>,
+}
+
+impl Snapshot {
+    pub(crate) fn update(&mut self, path: &str, sample: Sample, now: Instant) {
+        self.latest.insert(path.to_owned(), (sample, now));
+    }
+
+    // Forget the value of a signal the databroker has no value for anymore
+    pub(crate) fn remove(&mut self, path: &str) {
+        self.latest.remove(path);
+    }
+
+    // Latest sample of a signal and when it was received
+    pub(crate) fn get(&self, path: &str) -> Option<&(Sample, Instant)> {
+        self.latest.get(path)
+    }
+
+    // Build the aggregated message, signals without a value are included as null
+    pub(crate) async fn to_message(
+        &self,
+        signals: &[SignalMapping],
+        statuses: &HashMap<String, SignalStatus>,
+        metadata: &MetadataCache,
+        now: Instant,
+    ) -> SnapshotMessage {
+        let metadata = metadata
+            .get_all(signals.iter().map(|signal| signal.path.as_str()))
+            .await;
+        let mut fields = BTreeMap::new();
+        for signal in signals {
+            let unit = metadata
+                .get(signal.path.as_str())
+                .map(|metadata| metadata.unit.clone())
+                .filter(|unit| !unit.is_empty());
+            let status = statuses.get(&signal.path).copied().unwrap_or_default();
+            let field = match self.latest.get(&signal.path) {
+                Some(((value, timestamp), received)) => SnapshotField {
+                    value: json::value_to_json(value),
+                    unit,
+                    timestamp: *timestamp,
+                    age_ms: Some(now.duration_since(*received).as_millis()),
+                    status,
+                },
+                None => SnapshotField {
+                    value: serde_json::Value::Null,
+                    unit,
+                    timestamp: None,
+                    age_ms: None,
+                    status,
+                },
+            };
+            fields.insert(signal.path.clone(), field);
+        }
+
+        SnapshotMessage {
+            timestamp: json::now_millis(),
+            signals: fields,
+        }
+    }
+}
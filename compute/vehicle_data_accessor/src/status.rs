@@ -0,0 +1,77 @@
+// Copyright (c) 2025 Eclipse Foundation and others.
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::json;
+use crate::mapping::SignalMapping;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+// Whether the value of a signal can be taken as current
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum SignalStatus {
+    // Current value from the databroker
+    Valid,
+    // The databroker has no value for the signal
+    #[default]
+    NotAvailable,
+    // The last value is older than the stale age of the signal
+    Stale,
+    // The databroker is not reachable, the last value may be outdated
+    BrokerError,
+}
+
+// Status change of one signal, published on the status topic
+#[derive(Serialize, Debug)]
+pub(crate) struct StatusMessage {
+    path: String,
+    status: SignalStatus,
+    // Time of the change in milliseconds since the Unix epoch
+    timestamp: i64,
+}
+
+impl StatusMessage {
+    pub(crate) fn new(path: &str, status: SignalStatus) -> Self {
+        StatusMessage {
+            path: path.to_owned(),
+            status,
+            timestamp: json::now_millis(),
+        }
+    }
+}
+
+// Whether a sample is older than the stale age of its signal. The source timestamp is
+// preferred over the time of receipt, as polling returns an old value again and again.
+pub(crate) fn is_stale(
+    signal: &SignalMapping,
+    timestamp: Option<i64>,
+    received: Instant,
+    now: Instant,
+) -> bool {
+    let Some(stale_after_ms) = signal.stale_after_ms else {
+        return false;
+    };
+    let age = match timestamp {
+        Some(timestamp) => {
+            Duration::from_millis(u64::try_from(json::now_millis() - timestamp).unwrap_or(0))
+        }
+        None => now.duration_since(received),
+    };
+    age >= Duration::from_millis(stale_after_ms)
+}